@@ -14,33 +14,181 @@
 //
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::cell::RefCell;
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
 
 use gtk::{
+    gdk::Key,
     gio,
     glib::{self, clone, Continue, Receiver},
     prelude::*,
     subclass::prelude::*,
 };
-use log::info;
+use log::{info, warn};
 use pipewire::{channel::Sender, spa::Direction};
 
 use crate::{
+    patchbay::{self, NamedLink},
     view::{self},
-    GtkMessage, MediaType, NodeType, PipewireLink, PipewireMessage,
+    DeviceKind, GtkMessage, MediaType, NodeType, PipewireLink, PipewireMessage,
 };
 
 static STYLE: &str = include_str!("style.css");
+/// Overrides of the colors in [`STYLE`] for the light theme.
+static LIGHT_STYLE: &str = include_str!("style-light.css");
+
+/// Collect the commands shown in the command palette.
+///
+/// These are the items of the menu, labelled with the section they are in,
+/// followed by all other actions of the application and window that do not need a parameter.
+fn palette_commands(
+    app: &Application,
+    window: &gtk::ApplicationWindow,
+    menu: &gio::MenuModel,
+) -> Vec<view::Command> {
+    let accelerator = |action: &str| {
+        app.accels_for_action(action).first().and_then(|accel| {
+            let (key, modifiers) = gtk::accelerator_parse(accel)?;
+            Some(gtk::accelerator_get_label(key, modifiers).to_string())
+        })
+    };
+
+    let mut commands = Vec::new();
+    let mut menu_actions = HashSet::new();
+    let mut menus = VecDeque::from([(menu.clone(), None::<String>)]);
+    while let Some((menu, section)) = menus.pop_front() {
+        for i in 0..menu.n_items() {
+            if let Some(section_menu) = menu.item_link(i, "section") {
+                let label = menu
+                    .item_attribute_value(i, "label", Some(glib::VariantTy::STRING))
+                    .and_then(|label| label.get::<String>());
+                menus.push_back((section_menu, label));
+                continue;
+            }
+
+            let Some(label) = menu
+                .item_attribute_value(i, "label", Some(glib::VariantTy::STRING))
+                .and_then(|label| label.get::<String>())
+            else {
+                continue;
+            };
+            let Some(action) = menu
+                .item_attribute_value(i, "action", Some(glib::VariantTy::STRING))
+                .and_then(|action| action.get::<String>())
+            else {
+                continue;
+            };
+            let target = menu.item_attribute_value(i, "target", None);
+            let action = gio::Action::print_detailed_name(&action, target.as_ref()).to_string();
+
+            menu_actions.insert(action.clone());
+            commands.push(view::Command {
+                label: match section {
+                    Some(ref section) => format!("{}: {}", section, label),
+                    None => label.trim_end_matches('…').to_string(),
+                },
+                accelerator: accelerator(&action),
+                action,
+            });
+        }
+    }
+
+    // Actions that are not in the menu only have their name to describe them, e.g. "fit-view" becomes "Fit view".
+    let groups: [(&str, &gio::ActionGroup); 2] =
+        [("app", app.upcast_ref()), ("win", window.upcast_ref())];
+    for (prefix, group) in groups {
+        for name in group.list_actions() {
+            let action = format!("{}.{}", prefix, name);
+            if menu_actions.contains(&action)
+                || name == "command-palette"
+                || group.action_parameter_type(&name).is_some()
+            {
+                continue;
+            }
+
+            let mut label = name.replace('-', " ");
+            if let Some(first) = label.get_mut(..1) {
+                first.make_ascii_uppercase();
+            }
+            commands.push(view::Command {
+                label,
+                accelerator: accelerator(&action),
+                action,
+            });
+        }
+    }
+
+    commands
+}
+
+/// How many messages from the pipewire thread are processed in a single idle callback.
+///
+/// During startup, the pipewire thread sends a message for every node, port and link at once.
+/// Processing them in small batches lets the main loop draw frames in between, so the window stays
+/// responsive even on systems with hundreds of nodes.
+const MESSAGES_PER_IDLE: usize = 50;
+
+/// A link of a node whose connections were copied, described by names so it can be recreated on another node.
+#[derive(Debug, Clone)]
+struct CopiedConnection {
+    /// Direction of the port on the copied node.
+    direction: Direction,
+    /// Name of the port on the copied node.
+    port: String,
+    peer_node: String,
+    peer_port: String,
+}
 
 mod imp {
     use super::*;
 
-    use once_cell::unsync::OnceCell;
+    use once_cell::{sync::Lazy, unsync::OnceCell};
 
     #[derive(Default)]
     pub struct Application {
         pub(super) graphview: view::GraphView,
         pub(super) pw_sender: OnceCell<RefCell<Sender<GtkMessage>>>,
+        /// Messages received from the pipewire thread that have not been processed yet.
+        pub(super) pending_messages: RefCell<VecDeque<PipewireMessage>>,
+        /// Whether an idle callback for processing `pending_messages` is currently scheduled.
+        pub(super) processing_scheduled: Cell<bool>,
+        /// Spins while there are pending messages, e.g. during initial enumeration of the graph.
+        pub(super) spinner: gtk::Spinner,
+        /// Banner warning the user that the graph has grown large enough to slow down the view.
+        pub(super) size_warning: gtk::InfoBar,
+        /// Set when the user closes the size warning, so that it is not shown again this session.
+        pub(super) size_warning_dismissed: Cell<bool>,
+        /// Shows the number of nodes, ports and links on the graph at the bottom of the window.
+        pub(super) status_label: gtk::Label,
+        pub(super) node_warning_threshold: Cell<u32>,
+        pub(super) link_warning_threshold: Cell<u32>,
+        /// Port pairs of link toggles that were requested, but have not been reflected by the pipewire thread yet,
+        /// with the timeout that stops showing them as pending if that never happens.
+        pub(super) pending_links: RefCell<HashMap<(u32, u32), glib::SourceId>>,
+        /// Links of the latest patchbay import that could not be created yet, because their ports have not appeared.
+        ///
+        /// Importing again replaces the links still waiting from the previous import.
+        pub(super) imported_links: RefCell<Vec<NamedLink>>,
+        /// Whether the links of a node that disappears are created again once a node with the same name reappears.
+        pub(super) remember_routing: Cell<bool>,
+        /// Links of nodes that disappeared, which are created again once their ports reappear.
+        pub(super) remembered_links: RefCell<Vec<NamedLink>>,
+        /// Links that were removed since a node or port was last added, which are not the users doing.
+        ///
+        /// The links of a node that disappears are removed right before the node itself,
+        /// so they are taken from here once the node is removed.
+        pub(super) removed_links: RefCell<Vec<(PipewireLink, NamedLink)>>,
+        /// The most recently removed links, newest first, so that removing a link can be undone.
+        pub(super) link_history: RefCell<VecDeque<PipewireLink>>,
+        /// The connections copied from a node with "Copy Connections", to be pasted onto another node.
+        pub(super) copied_connections: RefCell<Vec<CopiedConnection>>,
+        /// Whether the dark variant of the theme is used.
+        pub(super) dark_theme: Cell<bool>,
+        /// Holds the colors of the light or dark theme, on top of the base stylesheet.
+        pub(super) theme_provider: gtk::CssProvider,
     }
 
     #[glib::object_subclass]
@@ -50,7 +198,56 @@ mod imp {
         type ParentType = gtk::Application;
     }
 
-    impl ObjectImpl for Application {}
+    impl ObjectImpl for Application {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![
+                    glib::ParamSpecUInt::builder("node-warning-threshold")
+                        .default_value(200)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecUInt::builder("link-warning-threshold")
+                        .default_value(500)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("remember-routing")
+                        .default_value(false)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("dark-theme")
+                        .default_value(true)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                ]
+            });
+
+            PROPERTIES.as_ref()
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "node-warning-threshold" => self.node_warning_threshold.get().to_value(),
+                "link-warning-threshold" => self.link_warning_threshold.get().to_value(),
+                "remember-routing" => self.remember_routing.get().to_value(),
+                "dark-theme" => self.dark_theme.get().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            match pspec.name() {
+                "node-warning-threshold" => self.node_warning_threshold.set(value.get().unwrap()),
+                "link-warning-threshold" => self.link_warning_threshold.set(value.get().unwrap()),
+                "remember-routing" => self.remember_routing.set(value.get().unwrap()),
+                "dark-theme" => {
+                    self.dark_theme.set(value.get().unwrap());
+                    self.update_theme();
+                }
+                _ => unimplemented!(),
+            }
+        }
+    }
+
     impl ApplicationImpl for Application {
         fn activate(&self) {
             let app = &*self.obj();
@@ -58,19 +255,259 @@ mod imp {
                 .child(&self.graphview)
                 .build();
             let headerbar = gtk::HeaderBar::new();
+            self.spinner.set_tooltip_text(Some("Loading graph…"));
+            headerbar.pack_start(&self.spinner);
+
+            // Toggles for showing and hiding nodes by their type.
+            let node_type_filter = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+            node_type_filter.add_css_class("linked");
+            for (label, tooltip, property) in [
+                ("Sources", "Show nodes that produce data", "show-sources"),
+                ("Sinks", "Show nodes that consume data", "show-sinks"),
+                (
+                    "Other",
+                    "Show nodes that are neither sources nor sinks",
+                    "show-other-nodes",
+                ),
+            ] {
+                let toggle = gtk::ToggleButton::with_label(label);
+                toggle.set_tooltip_text(Some(tooltip));
+                self.graphview
+                    .bind_property(property, &toggle, "active")
+                    .bidirectional()
+                    .sync_create()
+                    .build();
+                node_type_filter.append(&toggle);
+            }
+            headerbar.pack_start(&node_type_filter);
             let zoomentry = view::ZoomEntry::new(&self.graphview);
+            let zoom_scale = gtk::Scale::with_range(
+                gtk::Orientation::Horizontal,
+                view::GraphView::ZOOM_MIN,
+                view::GraphView::ZOOM_MAX,
+                view::GraphView::ZOOM_STEP,
+            );
+            zoom_scale.set_draw_value(false);
+            zoom_scale.set_width_request(120);
+            zoom_scale.set_tooltip_text(Some("Zoom"));
+            self.graphview
+                .bind_property("zoom-factor", &zoom_scale.adjustment(), "value")
+                .sync_create()
+                .build();
+            self.graphview
+                .bind_property("zoom-locked", &zoom_scale, "sensitive")
+                .invert_boolean()
+                .sync_create()
+                .build();
+            // Go through `set_zoom_factor` instead of binding both ways, so the zoom is clamped and anchored.
+            zoom_scale.connect_value_changed(
+                clone!(@weak self.graphview as graphview => move |scale| {
+                    if (scale.value() - graphview.zoom_factor()).abs() > f64::EPSILON {
+                        graphview.set_zoom_factor(scale.value(), None);
+                    }
+                }),
+            );
+
+            let view_menu = gio::Menu::new();
+            view_menu.append(Some("Show Link Endpoints"), Some("win.show-link-endpoints"));
+            view_menu.append(Some("Show Link Labels"), Some("win.show-link-labels"));
+            view_menu.append(Some("Draw Links Above Nodes"), Some("win.links-on-top"));
+            view_menu.append(
+                Some("Group Nodes by Application"),
+                Some("win.group-by-application"),
+            );
+            view_menu.append(Some("Dim Inactive Nodes"), Some("win.dim-inactive-nodes"));
+            view_menu.append(Some("Wrap Scrolling at Edges"), Some("win.wrap-scrolling"));
+            view_menu.append(Some("Show Link Density"), Some("win.show-link-density"));
+            view_menu.append(Some("Show Port Numbers"), Some("win.show-port-indices"));
+            view_menu.append(Some("Show Minimap"), Some("win.show-minimap"));
+            view_menu.append(Some("Show Diagnostics"), Some("win.show-diagnostics"));
+            view_menu.append(Some("Lock Zoom"), Some("win.zoom-locked"));
+            view_menu.append(
+                Some("Invert Zoom Scrolling"),
+                Some("win.invert-zoom-scroll"),
+            );
+            view_menu.append(Some("Animate Zooming"), Some("win.animate-zoom"));
+            view_menu.append(Some("Fit Graph to Window"), Some("app.fit-view"));
+            view_menu.append(Some("Fit Selection to Window"), Some("app.fit-selection"));
+            view_menu.append(Some("Arrange by Signal Flow"), Some("app.auto-layout"));
+            let align_section = gio::Menu::new();
+            align_section.append(Some("Align Left"), Some("app.align-left"));
+            align_section.append(Some("Align Top"), Some("app.align-top"));
+            align_section.append(
+                Some("Distribute Horizontally"),
+                Some("app.distribute-horizontally"),
+            );
+            align_section.append(
+                Some("Distribute Vertically"),
+                Some("app.distribute-vertically"),
+            );
+            align_section.append(Some("Tidy"), Some("app.tidy-selection"));
+            view_menu.append_section(Some("Arrange Selection"), &align_section);
+            view_menu.append(Some("Reset Layout…"), Some("app.reset-layout"));
+            view_menu.append(
+                Some("Import qpwgraph Patchbay…"),
+                Some("app.import-patchbay"),
+            );
+            view_menu.append(
+                Some("Restore Links of Reappearing Nodes"),
+                Some("app.remember-routing"),
+            );
+            view_menu.append(Some("Dark Theme"), Some("app.dark-theme"));
+            let layout_section = gio::Menu::new();
+            layout_section.append(Some("Left to Right"), Some("win.orientation::horizontal"));
+            layout_section.append(Some("Top to Bottom"), Some("win.orientation::vertical"));
+            view_menu.append_section(Some("Signal Flow"), &layout_section);
+            let highlight_section = gio::Menu::new();
+            highlight_section.append(Some("Nothing"), Some("win.node-highlight::none"));
+            highlight_section.append(Some("Sinks"), Some("win.node-highlight::sinks"));
+            highlight_section.append(Some("Sources"), Some("win.node-highlight::sources"));
+            highlight_section.append(Some("Audio Devices"), Some("win.node-highlight::devices"));
+            view_menu.append_section(Some("Highlight"), &highlight_section);
+            let link_filter_section = gio::Menu::new();
+            link_filter_section.append(Some("All"), Some("win.link-filter::all"));
+            link_filter_section.append(Some("Only Active"), Some("win.link-filter::active"));
+            link_filter_section.append(Some("Only Inactive"), Some("win.link-filter::inactive"));
+            view_menu.append_section(Some("Show Links"), &link_filter_section);
+            let inactive_link_section = gio::Menu::new();
+            inactive_link_section.append(Some("Dashed"), Some("win.inactive-link-style::dashed"));
+            inactive_link_section.append(Some("Dimmed"), Some("win.inactive-link-style::dimmed"));
+            inactive_link_section.append(Some("Hidden"), Some("win.inactive-link-style::hidden"));
+            view_menu.append_section(Some("Inactive Links"), &inactive_link_section);
+            let inactive_node_section = gio::Menu::new();
+            inactive_node_section.append(
+                Some("Dim Strongly"),
+                Some("win.inactive-node-opacity(0.25)"),
+            );
+            inactive_node_section.append(Some("Dim"), Some("win.inactive-node-opacity(0.5)"));
+            inactive_node_section.append(
+                Some("Dim Slightly"),
+                Some("win.inactive-node-opacity(0.75)"),
+            );
+            view_menu.append_section(Some("Inactive Nodes"), &inactive_node_section);
+            let link_style_section = gio::Menu::new();
+            link_style_section.append(Some("Curved"), Some("win.link-style::bezier"));
+            link_style_section.append(Some("Straight"), Some("win.link-style::straight"));
+            link_style_section.append(Some("Right Angles"), Some("win.link-style::orthogonal"));
+            view_menu.append_section(Some("Link Shape"), &link_style_section);
+            let menu_button = gtk::MenuButton::builder()
+                .icon_name("open-menu-symbolic")
+                .menu_model(&view_menu)
+                .build();
+            headerbar.pack_end(&menu_button);
+
+            let theme_toggle = gtk::ToggleButton::builder()
+                .icon_name("weather-clear-night-symbolic")
+                .tooltip_text("Dark Theme")
+                .build();
+            app.bind_property("dark-theme", &theme_toggle, "active")
+                .bidirectional()
+                .sync_create()
+                .build();
+            headerbar.pack_end(&theme_toggle);
             headerbar.pack_end(&zoomentry);
+            headerbar.pack_end(&zoom_scale);
+
+            let search_entry = gtk::SearchEntry::new();
+            search_entry.set_placeholder_text(Some("Find nodes…"));
+            search_entry.set_tooltip_text(Some(
+                "Highlight nodes by name, press Enter to jump to the first match",
+            ));
+            search_entry.connect_search_changed(
+                clone!(@weak self.graphview as graphview => move |entry| {
+                    graphview.set_search_query(&entry.text());
+                }),
+            );
+            search_entry.connect_activate(
+                clone!(@weak self.graphview as graphview => move |entry| {
+                    if entry.text().is_empty() {
+                        return;
+                    }
+                    if let Some(node_id) = graphview.nodes_matching(&entry.text()).first() {
+                        graphview.center_on_node(*node_id);
+                    }
+                }),
+            );
+            search_entry.connect_stop_search(|entry| entry.set_text(""));
+            headerbar.pack_end(&search_entry);
+
+            // The list of favorites is built whenever it is opened, so it reflects which favorites are present.
+            let favorites_button = gtk::MenuButton::builder()
+                .icon_name("starred-symbolic")
+                .tooltip_text("Favorite Nodes")
+                .build();
+            favorites_button.set_create_popup_func(
+                clone!(@weak self.graphview as graphview => move |button| {
+                    let list = gtk::Box::new(gtk::Orientation::Vertical, 0);
+                    let popover = gtk::Popover::builder().child(&list).build();
+
+                    let favorites = graphview.favorites();
+                    if favorites.is_empty() {
+                        let label = gtk::Label::new(Some("Mark nodes as favorites from their menu"));
+                        label.add_css_class("dim-label");
+                        list.append(&label);
+                    }
+                    for (name, id) in favorites {
+                        let item = gtk::Button::with_label(&name);
+                        item.add_css_class("flat");
+                        if let Some(label) = item.child().and_then(|child| child.downcast::<gtk::Label>().ok()) {
+                            label.set_xalign(0.0);
+                        }
+                        // Favorites that are not on the graph can not be jumped to.
+                        item.set_sensitive(id.is_some());
+                        item.connect_clicked(clone!(@weak graphview, @weak popover => move |_| {
+                            if let Some(id) = id {
+                                graphview.center_on_node(id);
+                            }
+                            popover.popdown();
+                        }));
+                        list.append(&item);
+                    }
+
+                    button.set_popover(Some(&popover));
+                }),
+            );
+            headerbar.pack_end(&favorites_button);
+
+            self.size_warning
+                .set_message_type(gtk::MessageType::Warning);
+            self.size_warning.set_show_close_button(true);
+            self.size_warning.set_revealed(false);
+            let size_warning_label = gtk::Label::new(Some(
+                "The graph contains a lot of nodes and links, which may make Helvum slow to respond. \
+                 Hide some of them with the Show Links filter in the View menu or the node type toggles.",
+            ));
+            size_warning_label.set_wrap(true);
+            self.size_warning.add_child(&size_warning_label);
+            self.size_warning
+                .connect_response(clone!(@weak app => move |infobar, response| {
+                    if response == gtk::ResponseType::Close {
+                        app.imp().size_warning_dismissed.set(true);
+                        infobar.set_revealed(false);
+                    }
+                }));
+
+            let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+            content.append(&self.size_warning);
+            content.append(&scrollwindow);
+            scrollwindow.set_vexpand(true);
+
+            self.status_label.set_xalign(0.0);
+            self.status_label.add_css_class("dim-label");
+            self.status_label.set_margin_start(6);
+            self.status_label.set_margin_end(6);
+            self.status_label.set_margin_top(3);
+            self.status_label.set_margin_bottom(3);
+            content.append(&self.status_label);
+            app.update_status();
 
             let window = gtk::ApplicationWindow::builder()
                 .application(app)
                 .default_width(1280)
                 .default_height(720)
                 .title("Helvum - Pipewire Patchbay")
-                .child(&scrollwindow)
+                .child(&content)
                 .build();
-            window
-                .settings()
-                .set_gtk_application_prefer_dark_theme(true);
             window.set_titlebar(Some(&headerbar));
 
             let zoom_set_action =
@@ -83,6 +520,209 @@ mod imp {
             );
             window.add_action(&zoom_set_action);
 
+            let reset_layout_action = gio::SimpleAction::new("reset-layout", None);
+            reset_layout_action.connect_activate(
+                clone!(@weak window, @weak self.graphview as graphview => move |_, _| {
+                    // Node positions may have been arranged by hand, so make sure the user really wants to lose them.
+                    let dialog = gtk::MessageDialog::builder()
+                        .transient_for(&window)
+                        .modal(true)
+                        .message_type(gtk::MessageType::Question)
+                        .text("Reset the layout?")
+                        .secondary_text("All nodes will be moved back to their default positions.")
+                        .build();
+                    dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+                    dialog.add_button("Reset", gtk::ResponseType::Accept);
+
+                    dialog.connect_response(clone!(@weak graphview => move |dialog, response| {
+                        if response == gtk::ResponseType::Accept {
+                            graphview.reset_layout();
+                        }
+                        dialog.close();
+                    }));
+                    dialog.show();
+                }),
+            );
+            app.add_action(&reset_layout_action);
+
+            let import_patchbay_action = gio::SimpleAction::new("import-patchbay", None);
+            import_patchbay_action.connect_activate(
+                clone!(@weak window, @weak app => move |_, _| {
+                    let dialog = gtk::FileChooserDialog::new(
+                        Some("Import qpwgraph Patchbay"),
+                        Some(&window),
+                        gtk::FileChooserAction::Open,
+                        &[
+                            ("Cancel", gtk::ResponseType::Cancel),
+                            ("Import", gtk::ResponseType::Accept),
+                        ],
+                    );
+                    dialog.set_modal(true);
+
+                    let filter = gtk::FileFilter::new();
+                    filter.set_name(Some("qpwgraph Patchbays"));
+                    filter.add_pattern("*.qpwgraph");
+                    dialog.add_filter(&filter);
+
+                    dialog.connect_response(clone!(@weak app => move |dialog, response| {
+                        if response == gtk::ResponseType::Accept {
+                            if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                                app.import_patchbay(&path);
+                            }
+                        }
+                        dialog.close();
+                    }));
+                    dialog.show();
+                }),
+            );
+            app.add_action(&import_patchbay_action);
+            app.add_action(&gio::PropertyAction::new(
+                "remember-routing",
+                app,
+                "remember-routing",
+            ));
+            app.add_action(&gio::PropertyAction::new("dark-theme", app, "dark-theme"));
+
+            let command_palette_action = gio::SimpleAction::new("command-palette", None);
+            command_palette_action.connect_activate(
+                clone!(@weak window, @weak app, @strong view_menu => move |_, _| {
+                    let commands = palette_commands(&app, &window, view_menu.upcast_ref());
+                    view::CommandPalette::new(&window, commands).present();
+                }),
+            );
+            app.set_accels_for_action("app.command-palette", &["<Control><Shift>P"]);
+            app.add_action(&command_palette_action);
+
+            // Leave the focus on a single port with Escape.
+            let focus_keys = gtk::EventControllerKey::new();
+            focus_keys.connect_key_pressed(
+                clone!(@weak self.graphview as graphview => @default-return gtk::Inhibit(false),
+                    move |_, keyval, _, _| {
+                        if keyval != Key::Escape || graphview.focused_port().is_none() {
+                            return gtk::Inhibit(false);
+                        }
+
+                        graphview.set_focused_port(None);
+                        gtk::Inhibit(true)
+                    }
+                ),
+            );
+            window.add_controller(focus_keys);
+
+            // Shortcuts using keys that text entries need are only handled while the graph has the focus,
+            // so that e.g. typing a capital F or undoing an edit in the search entry or a note still works.
+            let graph_shortcuts = gtk::ShortcutController::new();
+            graph_shortcuts.add_shortcut(gtk::Shortcut::new(
+                gtk::ShortcutTrigger::parse_string("<Shift>F"),
+                Some(gtk::NamedAction::new("app.fit-selection")),
+            ));
+            graph_shortcuts.add_shortcut(gtk::Shortcut::new(
+                gtk::ShortcutTrigger::parse_string("<Control>Z"),
+                Some(gtk::NamedAction::new("app.undo-disconnect")),
+            ));
+            self.graphview.add_controller(graph_shortcuts);
+
+            window.add_action(&gio::PropertyAction::new(
+                "show-link-endpoints",
+                &self.graphview,
+                "show-link-endpoints",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "show-link-labels",
+                &self.graphview,
+                "show-link-labels",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "links-on-top",
+                &self.graphview,
+                "links-on-top",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "group-by-application",
+                &self.graphview,
+                "group-by-application",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "show-grid",
+                &self.graphview,
+                "show-grid",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "dim-inactive-nodes",
+                &self.graphview,
+                "dim-inactive-nodes",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "inactive-node-opacity",
+                &self.graphview,
+                "inactive-node-opacity",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "wrap-scrolling",
+                &self.graphview,
+                "wrap-scrolling",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "show-link-density",
+                &self.graphview,
+                "show-link-density",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "show-minimap",
+                &self.graphview,
+                "show-minimap",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "show-diagnostics",
+                &self.graphview,
+                "show-diagnostics",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "show-port-indices",
+                &self.graphview,
+                "show-port-indices",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "invert-zoom-scroll",
+                &self.graphview,
+                "invert-zoom-scroll",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "animate-zoom",
+                &self.graphview,
+                "animate-zoom",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "zoom-locked",
+                &self.graphview,
+                "zoom-locked",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "node-highlight",
+                &self.graphview,
+                "node-highlight",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "link-filter",
+                &self.graphview,
+                "link-filter",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "inactive-link-style",
+                &self.graphview,
+                "inactive-link-style",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "link-style",
+                &self.graphview,
+                "link-style",
+            ));
+            window.add_action(&gio::PropertyAction::new(
+                "orientation",
+                &self.graphview,
+                "orientation",
+            ));
+
             window.show();
         }
 
@@ -90,13 +730,47 @@ mod imp {
             self.parent_startup();
 
             // Load CSS from the STYLE variable.
+            let display =
+                gtk::gdk::Display::default().expect("Error initializing gtk css provider.");
             let provider = gtk::CssProvider::new();
             provider.load_from_data(STYLE);
             gtk::StyleContext::add_provider_for_display(
-                &gtk::gdk::Display::default().expect("Error initializing gtk css provider."),
+                &display,
                 &provider,
                 gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
             );
+            // The theme colors override the ones of the base stylesheet.
+            gtk::StyleContext::add_provider_for_display(
+                &display,
+                &self.theme_provider,
+                gtk::STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+            );
+        }
+
+        fn shutdown(&self) {
+            // Pending links do not need to time out anymore.
+            for (_, timeout) in self.pending_links.take() {
+                timeout.remove();
+            }
+
+            self.parent_shutdown();
+        }
+    }
+
+    impl Application {
+        /// Switch between the dark and light variant of the theme.
+        fn update_theme(&self) {
+            let dark = self.dark_theme.get();
+
+            if let Some(settings) = gtk::Settings::default() {
+                settings.set_gtk_application_prefer_dark_theme(dark);
+            }
+            // The base stylesheet is made for the dark theme, so only the light theme needs different colors.
+            self.theme_provider
+                .load_from_data(if dark { "" } else { LIGHT_STYLE });
+
+            // Links and the grid are drawn with colors looked up from the theme.
+            self.graphview.queue_draw();
         }
     }
     impl GtkApplicationImpl for Application {}
@@ -134,21 +808,206 @@ impl Application {
         app.set_accels_for_action("app.quit", &["<Control>Q"]);
         app.add_action(&quit);
 
+        // Add <Control-F> shortcut for showing the whole graph.
+        let fit_view = gtk::gio::SimpleAction::new("fit-view", None);
+        fit_view.connect_activate(clone!(@weak app => move |_, _| {
+            app.imp().graphview.fit_to_view();
+        }));
+        app.set_accels_for_action("app.fit-view", &["<Control>F"]);
+        app.add_action(&fit_view);
+
+        // Show only the selected nodes, which the graph does on <Shift-F>.
+        let fit_selection = gtk::gio::SimpleAction::new("fit-selection", None);
+        fit_selection.connect_activate(clone!(@weak app => move |_, _| {
+            let graphview = &app.imp().graphview;
+            graphview.fit_to_nodes(&graphview.selected_nodes());
+        }));
+        app.add_action(&fit_selection);
+
+        let align_left = gtk::gio::SimpleAction::new("align-left", None);
+        align_left.connect_activate(clone!(@weak app => move |_, _| {
+            app.imp().graphview.align_selected_nodes_left();
+        }));
+        app.add_action(&align_left);
+
+        let align_top = gtk::gio::SimpleAction::new("align-top", None);
+        align_top.connect_activate(clone!(@weak app => move |_, _| {
+            app.imp().graphview.align_selected_nodes_top();
+        }));
+        app.add_action(&align_top);
+
+        let distribute_horizontally = gtk::gio::SimpleAction::new("distribute-horizontally", None);
+        distribute_horizontally.connect_activate(clone!(@weak app => move |_, _| {
+            app.imp()
+                .graphview
+                .distribute_selected_nodes(gtk::Orientation::Horizontal);
+        }));
+        app.add_action(&distribute_horizontally);
+
+        let distribute_vertically = gtk::gio::SimpleAction::new("distribute-vertically", None);
+        distribute_vertically.connect_activate(clone!(@weak app => move |_, _| {
+            app.imp()
+                .graphview
+                .distribute_selected_nodes(gtk::Orientation::Vertical);
+        }));
+        app.add_action(&distribute_vertically);
+
+        let tidy_selection = gtk::gio::SimpleAction::new("tidy-selection", None);
+        tidy_selection.connect_activate(clone!(@weak app => move |_, _| {
+            app.imp().graphview.tidy_selected_nodes();
+        }));
+        app.add_action(&tidy_selection);
+
+        // Recreate the most recently removed link, which the graph does on <Control-Z>.
+        let undo_disconnect = gtk::gio::SimpleAction::new("undo-disconnect", None);
+        undo_disconnect.connect_activate(clone!(@weak app => move |_, _| {
+            app.undo_disconnect();
+        }));
+        app.add_action(&undo_disconnect);
+
+        // Create the links of a qpwgraph patchbay that was copied to the clipboard, e.g. from a patchbay file.
+        let paste_links = gtk::gio::SimpleAction::new("paste-links", None);
+        paste_links.connect_activate(clone!(@weak app => move |_, _| {
+            app.imp().graphview.clipboard().read_text_async(
+                gio::Cancellable::NONE,
+                clone!(@weak app => move |text| match text {
+                    Ok(Some(text)) => app.import_links(&text, "the clipboard"),
+                    Ok(None) => info!("The clipboard contains no links to paste"),
+                    Err(e) => warn!("Failed to read the clipboard: {}", e),
+                }),
+            );
+        }));
+        app.add_action(&paste_links);
+
+        // Add <Control-L> shortcut for arranging the nodes by how signals flow between them.
+        let auto_layout = gtk::gio::SimpleAction::new("auto-layout", None);
+        auto_layout.connect_activate(clone!(@weak app => move |_, _| {
+            app.imp().graphview.auto_layout();
+        }));
+        app.set_accels_for_action("app.auto-layout", &["<Control>L"]);
+        app.add_action(&auto_layout);
+
+        // Add <Control-0> shortcut for resetting the zoom, and <Control-Plus> and <Control-Minus> for zooming in steps.
+        let reset_zoom = gtk::gio::SimpleAction::new("reset-zoom", None);
+        reset_zoom.connect_activate(clone!(@weak app => move |_, _| {
+            app.imp().graphview.set_zoom_factor(1.0, None);
+        }));
+        app.set_accels_for_action("app.reset-zoom", &["<Control>0", "<Control>KP_0"]);
+        app.add_action(&reset_zoom);
+
+        let zoom_in = gtk::gio::SimpleAction::new("zoom-in", None);
+        zoom_in.connect_activate(clone!(@weak app => move |_, _| {
+            let graphview = &app.imp().graphview;
+            graphview.set_zoom_factor(graphview.zoom_factor() + view::GraphView::ZOOM_STEP, None);
+        }));
+        app.set_accels_for_action(
+            "app.zoom-in",
+            &["<Control>plus", "<Control>equal", "<Control>KP_Add"],
+        );
+        app.add_action(&zoom_in);
+
+        let zoom_out = gtk::gio::SimpleAction::new("zoom-out", None);
+        zoom_out.connect_activate(clone!(@weak app => move |_, _| {
+            let graphview = &app.imp().graphview;
+            graphview.set_zoom_factor(graphview.zoom_factor() - view::GraphView::ZOOM_STEP, None);
+        }));
+        app.set_accels_for_action("app.zoom-out", &["<Control>minus", "<Control>KP_Subtract"]);
+        app.add_action(&zoom_out);
+
+        // Holding Shift as well zooms in smaller steps for precise framing.
+        // These accelerators match the keys exactly, so they take precedence over the coarse ones above.
+        let zoom_in_fine = gtk::gio::SimpleAction::new("zoom-in-fine", None);
+        zoom_in_fine.connect_activate(clone!(@weak app => move |_, _| {
+            let graphview = &app.imp().graphview;
+            graphview.set_zoom_factor(
+                graphview.zoom_factor() + view::GraphView::ZOOM_STEP_FINE,
+                None,
+            );
+        }));
+        app.set_accels_for_action(
+            "app.zoom-in-fine",
+            &["<Control><Shift>plus", "<Control><Shift>KP_Add"],
+        );
+        app.add_action(&zoom_in_fine);
+
+        let zoom_out_fine = gtk::gio::SimpleAction::new("zoom-out-fine", None);
+        zoom_out_fine.connect_activate(clone!(@weak app => move |_, _| {
+            let graphview = &app.imp().graphview;
+            graphview.set_zoom_factor(
+                graphview.zoom_factor() - view::GraphView::ZOOM_STEP_FINE,
+                None,
+            );
+        }));
+        app.set_accels_for_action(
+            "app.zoom-out-fine",
+            &["<Control><Shift>underscore", "<Control><Shift>KP_Subtract"],
+        );
+        app.add_action(&zoom_out_fine);
+
+        // Add <Control-I> shortcut for cycling through the ways inactive links are drawn.
+        let cycle_inactive_link_style =
+            gtk::gio::SimpleAction::new("cycle-inactive-link-style", None);
+        cycle_inactive_link_style.connect_activate(clone!(@weak app => move |_, _| {
+            let graphview = &app.imp().graphview;
+            graphview.set_inactive_link_style(graphview.inactive_link_style().next());
+        }));
+        app.set_accels_for_action("app.cycle-inactive-link-style", &["<Control>I"]);
+        app.add_action(&cycle_inactive_link_style);
+
+        // Warn about a large graph as soon as it grows too large.
+        app.imp().graphview.connect_local(
+            "graph-changed",
+            false,
+            clone!(@weak app => @default-return None, move |_| {
+                app.check_graph_size();
+
+                None
+            }),
+        );
+
+        // Remove a link if it was clicked on the graph.
+        app.imp().graphview.connect_local(
+            "link-remove-requested",
+            false,
+            clone!(@weak app => @default-return None, move |args| {
+                // Args always look like this: &[widget, id_port_from, id_port_to]
+                let port_from = args[1].get::<u32>().unwrap();
+                let port_to = args[2].get::<u32>().unwrap();
+
+                app.toggle_link(port_from, port_to);
+
+                None
+            }),
+        );
+
+        // Move one end of a link if it was dragged to another port.
+        app.imp().graphview.connect_local(
+            "link-reroute-requested",
+            false,
+            clone!(@weak app => @default-return None, move |args| {
+                // Args always look like this: &[widget, old_port_from, old_port_to, new_port_from, new_port_to]
+                let old_port_from = args[1].get::<u32>().unwrap();
+                let old_port_to = args[2].get::<u32>().unwrap();
+                let new_port_from = args[3].get::<u32>().unwrap();
+                let new_port_to = args[4].get::<u32>().unwrap();
+
+                app.toggle_link(old_port_from, old_port_to);
+                // Toggling a link that already exists would remove it instead.
+                if !app.imp().graphview.has_link(new_port_from, new_port_to) {
+                    app.toggle_link(new_port_from, new_port_to);
+                }
+
+                None
+            }),
+        );
+
         // React to messages received from the pipewire thread.
         gtk_receiver.attach(
             None,
             clone!(
                 @weak app => @default-return Continue(true),
                 move |msg| {
-                    match msg {
-                        PipewireMessage::NodeAdded{ id, name, node_type } => app.add_node(id, name.as_str(), node_type),
-                        PipewireMessage::PortAdded{ id, node_id, name, direction, media_type } => app.add_port(id, name.as_str(), node_id, direction, media_type),
-                        PipewireMessage::LinkAdded{ id, node_from, port_from, node_to, port_to, active} => app.add_link(id, node_from, port_from, node_to, port_to, active),
-                        PipewireMessage::LinkStateChanged { id, active } => app.link_state_changed(id, active), // TODO
-                        PipewireMessage::NodeRemoved { id } => app.remove_node(id),
-                        PipewireMessage::PortRemoved { id, node_id } => app.remove_port(id, node_id),
-                        PipewireMessage::LinkRemoved { id } => app.remove_link(id)
-                    };
+                    app.queue_message(msg);
                     Continue(true)
                 }
             ),
@@ -157,13 +1016,317 @@ impl Application {
         app
     }
 
-    /// Add a new node to the view.
-    fn add_node(&self, id: u32, name: &str, node_type: Option<NodeType>) {
-        info!("Adding node to graph: id {}", id);
+    /// Show the frame rate, rendering time and size of the graph on top of the graph.
+    ///
+    /// This is used by the `--debug` flag.
+    pub(super) fn show_diagnostics(&self) {
+        self.imp().graphview.set_property("show-diagnostics", true);
+    }
+
+    /// Periodically print the frame rate and size of the graph to stdout.
+    ///
+    /// This is used by the benchmark mode to measure rendering performance while panning and zooming.
+    pub(super) fn report_frame_timings(&self) {
+        let last_report = Cell::new(0);
 
         self.imp()
             .graphview
-            .add_node(id, view::Node::new(name, id), node_type);
+            .add_tick_callback(move |graphview, frame_clock| {
+                // Frame times are in microseconds.
+                let frame_time = frame_clock.frame_time();
+                if frame_time - last_report.get() >= 1_000_000 {
+                    last_report.set(frame_time);
+                    println!(
+                        "{:.1} fps, {} nodes, {} links",
+                        frame_clock.fps(),
+                        graphview.node_count(),
+                        graphview.link_count()
+                    );
+                }
+
+                Continue(true)
+            });
+    }
+
+    /// Queue a message from the pipewire thread to be processed once the main loop is idle.
+    fn queue_message(&self, msg: PipewireMessage) {
+        let imp = self.imp();
+
+        imp.pending_messages.borrow_mut().push_back(msg);
+
+        if !imp.processing_scheduled.replace(true) {
+            imp.spinner.start();
+            glib::idle_add_local(clone!(
+                @weak self as app => @default-return Continue(false),
+                move || app.process_pending_messages()
+            ));
+        }
+    }
+
+    /// Process a batch of pending messages.
+    ///
+    /// Returns whether the idle callback should be called again because there are still messages left.
+    fn process_pending_messages(&self) -> Continue {
+        let imp = self.imp();
+
+        for _ in 0..MESSAGES_PER_IDLE {
+            // Don't keep the queue borrowed while handling the message.
+            let msg = imp.pending_messages.borrow_mut().pop_front();
+            match msg {
+                Some(msg) => self.handle_message(msg),
+                None => break,
+            }
+        }
+
+        if imp.pending_messages.borrow().is_empty() {
+            imp.processing_scheduled.set(false);
+            imp.spinner.stop();
+            Continue(false)
+        } else {
+            Continue(true)
+        }
+    }
+
+    /// Show a warning if the graph has grown beyond the configured node or link thresholds,
+    /// unless the user has already dismissed it.
+    fn check_graph_size(&self) {
+        let imp = self.imp();
+
+        if imp.size_warning_dismissed.get() {
+            return;
+        }
+
+        let too_large = imp.graphview.node_count() > imp.node_warning_threshold.get() as usize
+            || imp.graphview.link_count() > imp.link_warning_threshold.get() as usize;
+        imp.size_warning.set_revealed(too_large);
+    }
+
+    fn handle_message(&self, msg: PipewireMessage) {
+        match msg {
+            PipewireMessage::NodeAdded {
+                id,
+                name,
+                node_name,
+                media_name,
+                node_type,
+                device_kind,
+                application_name,
+            } => self.add_node(
+                id,
+                name.as_str(),
+                node_name.as_str(),
+                media_name.as_deref(),
+                node_type,
+                device_kind,
+                application_name.as_deref(),
+            ),
+            PipewireMessage::PortAdded {
+                id,
+                node_id,
+                name,
+                direction,
+                media_type,
+                index,
+            } => self.add_port(id, name.as_str(), node_id, direction, media_type, index),
+            PipewireMessage::LinkAdded {
+                id,
+                node_from,
+                port_from,
+                node_to,
+                port_to,
+                active,
+                media_type,
+            } => self.add_link(
+                id,
+                PipewireLink {
+                    node_from,
+                    port_from,
+                    node_to,
+                    port_to,
+                    media_type,
+                },
+                active,
+            ),
+            PipewireMessage::LinkStateChanged { id, active } => self.link_state_changed(id, active), // TODO
+            PipewireMessage::LinkCreationFailed { port_from, port_to } => {
+                self.clear_pending_link(port_from, port_to)
+            }
+            PipewireMessage::NodeStateChanged { id, active } => self.node_state_changed(id, active),
+            PipewireMessage::DefaultDeviceChanged { id, kind } => {
+                self.imp().graphview.set_default_device(kind, id)
+            }
+            PipewireMessage::NodeRemoved { id } => self.remove_node(id),
+            PipewireMessage::PortRemoved { id, node_id } => self.remove_port(id, node_id),
+            PipewireMessage::LinkRemoved { id } => self.remove_link(id),
+        };
+
+        self.update_status();
+    }
+
+    /// Show the current number of nodes, ports and links in the status bar.
+    fn update_status(&self) {
+        let imp = self.imp();
+        imp.status_label.set_text(&format!(
+            "{} nodes, {} ports, {} links",
+            imp.graphview.node_count(),
+            imp.graphview.port_count(),
+            imp.graphview.link_count()
+        ));
+    }
+
+    /// Add a new node to the view.
+    fn add_node(
+        &self,
+        id: u32,
+        name: &str,
+        node_name: &str,
+        media_name: Option<&str>,
+        node_type: Option<NodeType>,
+        device_kind: Option<DeviceKind>,
+        application_name: Option<&str>,
+    ) {
+        info!("Adding node to graph: id {}", id);
+
+        // Links that were removed before the graph grew again did not disappear together with a node.
+        self.imp().removed_links.borrow_mut().clear();
+
+        let node = view::Node::new(name, media_name, id);
+        node.set_node_name(node_name);
+        node.set_device_kind(device_kind);
+        node.set_application_name(application_name);
+
+        // Make the node the default device if the user chose "Set as Default" from its menu.
+        if let Some(kind) = device_kind {
+            node.connect_local(
+                "set-default",
+                false,
+                clone!(@weak self as app => @default-return None, move |_| {
+                    app.set_default_device(id, kind);
+
+                    None
+                }),
+            );
+        }
+
+        // Remove all links of the node if the user chose "Disconnect All" from its menu.
+        node.connect_local(
+            "disconnect-all",
+            false,
+            clone!(@weak self as app => @default-return None, move |_| {
+                for link in app.imp().graphview.links_for_node(id) {
+                    app.toggle_link(link.port_from, link.port_to);
+                }
+
+                None
+            }),
+        );
+
+        // Copy the links of the node, or recreate copied links on it, if the user chose so from its menu.
+        node.connect_local(
+            "copy-connections",
+            false,
+            clone!(@weak self as app => @default-return None, move |_| {
+                app.copy_connections(id);
+
+                None
+            }),
+        );
+        node.connect_local(
+            "paste-connections",
+            false,
+            clone!(@weak self as app => @default-return None, move |_| {
+                app.paste_connections(id);
+
+                None
+            }),
+        );
+
+        self.imp().graphview.add_node(id, node, node_type);
+    }
+
+    /// Remember the links of the node with the specified id by the names of their ports,
+    /// so that they can be pasted onto another node.
+    fn copy_connections(&self, node_id: u32) {
+        let graphview = &self.imp().graphview;
+
+        let connections: Vec<CopiedConnection> = graphview
+            .links_for_node(node_id)
+            .into_iter()
+            .filter_map(|link| {
+                let (node_from, port_from) =
+                    graphview.port_names(link.node_from, link.port_from)?;
+                let (node_to, port_to) = graphview.port_names(link.node_to, link.port_to)?;
+                Some(if link.node_from == node_id {
+                    CopiedConnection {
+                        direction: Direction::Output,
+                        port: port_from,
+                        peer_node: node_to,
+                        peer_port: port_to,
+                    }
+                } else {
+                    CopiedConnection {
+                        direction: Direction::Input,
+                        port: port_to,
+                        peer_node: node_from,
+                        peer_port: port_from,
+                    }
+                })
+            })
+            .collect();
+
+        info!(
+            "Copied {} connections of node {}",
+            connections.len(),
+            node_id
+        );
+        *self.imp().copied_connections.borrow_mut() = connections;
+    }
+
+    /// Create the copied links on the node with the specified id, using its ports with the same names
+    /// as the ports of the copied node.
+    fn paste_connections(&self, node_id: u32) {
+        let imp = self.imp();
+        let connections = imp.copied_connections.borrow().clone();
+        if connections.is_empty() {
+            info!("No connections copied that could be pasted");
+            return;
+        }
+
+        let mut unmatched = 0;
+        for connection in connections {
+            let peer_direction = match connection.direction {
+                Direction::Output => Direction::Input,
+                Direction::Input => Direction::Output,
+            };
+            let port =
+                imp.graphview
+                    .port_id_on_node(node_id, &connection.port, connection.direction);
+            let peer_port = imp.graphview.port_id_by_name(
+                &connection.peer_node,
+                &connection.peer_port,
+                peer_direction,
+            );
+            let (Some(port), Some(peer_port)) = (port, peer_port) else {
+                unmatched += 1;
+                continue;
+            };
+
+            let (port_from, port_to) = match connection.direction {
+                Direction::Output => (port, peer_port),
+                Direction::Input => (peer_port, port),
+            };
+            // Toggling a link that already exists would remove it instead.
+            if !imp.graphview.has_link(port_from, port_to) {
+                self.toggle_link(port_from, port_to);
+            }
+        }
+
+        if unmatched > 0 {
+            warn!(
+                "{} copied connections could not be pasted onto node {}, as no ports with matching names exist",
+                unmatched, node_id
+            );
+        }
     }
 
     /// Add a new port to the view.
@@ -174,10 +1337,11 @@ impl Application {
         node_id: u32,
         direction: Direction,
         media_type: Option<MediaType>,
+        index: Option<u32>,
     ) {
         info!("Adding port to graph: id {}", id);
 
-        let port = view::Port::new(id, name, direction, media_type);
+        let port = view::Port::new(id, name, direction, media_type, index);
 
         // Create or delete a link if the widget emits the "port-toggled" signal.
         port.connect_local(
@@ -195,33 +1359,80 @@ impl Application {
         );
 
         self.imp().graphview.add_port(node_id, id, port);
+
+        // The port may be needed for a link that was imported before it appeared.
+        self.create_imported_links();
+    }
+
+    /// Import the links of a qpwgraph patchbay file.
+    fn import_patchbay(&self, path: &std::path::Path) {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                warn!("Failed to read patchbay file {}: {}", path.display(), e);
+                return;
+            }
+        };
+
+        self.import_links(&contents, &path.display().to_string());
+    }
+
+    /// Import the links of a qpwgraph patchbay, whose contents were read from `source`.
+    ///
+    /// Links whose ports are not on the graph yet are created once the ports appear.
+    fn import_links(&self, contents: &str, source: &str) {
+        let Some(links) = patchbay::parse_qpwgraph(contents) else {
+            warn!("{} does not contain a qpwgraph patchbay", source);
+            return;
+        };
+
+        info!("Importing {} links from {}", links.len(), source);
+        *self.imp().imported_links.borrow_mut() = links;
+        self.create_imported_links();
+    }
+
+    /// Create all imported and remembered links whose ports are on the graph, and forget about them afterwards.
+    fn create_imported_links(&self) {
+        let imp = self.imp();
+
+        let mut ready = Vec::new();
+        for waiting in [&imp.imported_links, &imp.remembered_links] {
+            waiting.borrow_mut().retain(|link| {
+                let port_from = imp.graphview.port_id_by_name(
+                    &link.node_from,
+                    &link.port_from,
+                    Direction::Output,
+                );
+                let port_to =
+                    imp.graphview
+                        .port_id_by_name(&link.node_to, &link.port_to, Direction::Input);
+
+                match (port_from, port_to) {
+                    (Some(port_from), Some(port_to)) => {
+                        ready.push((port_from, port_to));
+                        false
+                    }
+                    _ => true,
+                }
+            });
+        }
+
+        // Toggling a link that already exists would remove it instead.
+        for (port_from, port_to) in ready {
+            if !imp.graphview.has_link(port_from, port_to) {
+                self.toggle_link(port_from, port_to);
+            }
+        }
     }
 
     /// Add a new link to the view.
-    fn add_link(
-        &self,
-        id: u32,
-        node_from: u32,
-        port_from: u32,
-        node_to: u32,
-        port_to: u32,
-        active: bool,
-    ) {
+    fn add_link(&self, id: u32, link: PipewireLink, active: bool) {
         info!("Adding link to graph: id {}", id);
 
-        // FIXME: Links should be colored depending on the data they carry (video, audio, midi) like ports are.
+        self.clear_pending_link(link.port_from, link.port_to);
 
         // Update graph to contain the new link.
-        self.imp().graphview.add_link(
-            id,
-            PipewireLink {
-                node_from,
-                port_from,
-                node_to,
-                port_to,
-            },
-            active,
-        );
+        self.imp().graphview.add_link(id, link, active);
     }
 
     fn link_state_changed(&self, id: u32, active: bool) {
@@ -234,8 +1445,62 @@ impl Application {
         self.imp().graphview.set_link_state(id, active);
     }
 
+    fn node_state_changed(&self, id: u32, active: bool) {
+        info!(
+            "Node state changed: Node (id={}) is now {}",
+            id,
+            if active { "active" } else { "inactive" }
+        );
+
+        self.imp().graphview.set_node_state(id, active);
+    }
+
     // Toggle a link between the two specified ports on the remote pipewire server.
     fn toggle_link(&self, port_from: u32, port_to: u32) {
+        // Show the ports as pending until the link appears or disappears.
+        // In case no response arrives, e.g. because the server refused the request, stop after a while.
+        const PENDING_TIMEOUT: Duration = Duration::from_secs(5);
+
+        let imp = self.imp();
+        if !imp
+            .pending_links
+            .borrow()
+            .contains_key(&(port_from, port_to))
+        {
+            imp.graphview.set_link_pending(port_from, port_to, true);
+            let timeout = glib::timeout_add_local_once(
+                PENDING_TIMEOUT,
+                clone!(@weak self as app => move || {
+                    // The timeout is already gone once it ran, so it must not be removed by clear_pending_link.
+                    let imp = app.imp();
+                    if imp.pending_links.borrow_mut().remove(&(port_from, port_to)).is_some() {
+                        imp.graphview.set_link_pending(port_from, port_to, false);
+                    }
+                }),
+            );
+            imp.pending_links
+                .borrow_mut()
+                .insert((port_from, port_to), timeout);
+        }
+
+        let sender = imp.pw_sender.get().expect("pw_sender not set").borrow_mut();
+        sender
+            .send(GtkMessage::ToggleLink { port_from, port_to })
+            .expect("Failed to send message");
+    }
+
+    /// Stop showing the ports of a requested link toggle as pending.
+    fn clear_pending_link(&self, port_from: u32, port_to: u32) {
+        let imp = self.imp();
+        let timeout = imp.pending_links.borrow_mut().remove(&(port_from, port_to));
+        if let Some(timeout) = timeout {
+            timeout.remove();
+            imp.graphview.set_link_pending(port_from, port_to, false);
+        }
+    }
+
+    /// Make the node with the specified id the default device on the remote pipewire server.
+    fn set_default_device(&self, id: u32, kind: DeviceKind) {
         let sender = self
             .imp()
             .pw_sender
@@ -243,7 +1508,7 @@ impl Application {
             .expect("pw_sender not set")
             .borrow_mut();
         sender
-            .send(GtkMessage::ToggleLink { port_from, port_to })
+            .send(GtkMessage::SetDefaultDevice { id, kind })
             .expect("Failed to send message");
     }
 
@@ -251,7 +1516,44 @@ impl Application {
     fn remove_node(&self, id: u32) {
         info!("Removing node from graph: id {}", id);
 
-        self.imp().graphview.remove_node(id);
+        let imp = self.imp();
+        if imp.remember_routing.get() {
+            self.remember_links_of(id);
+        }
+
+        imp.graphview.remove_node(id);
+    }
+
+    /// Queue the links that were removed together with the node with the specified id to be created again
+    /// once a node with the same name reappears.
+    fn remember_links_of(&self, node_id: u32) {
+        let imp = self.imp();
+        let mut remembered = Vec::new();
+        imp.removed_links.borrow_mut().retain(|(link, named_link)| {
+            if link.node_from == node_id || link.node_to == node_id {
+                remembered.push(named_link.clone());
+                false
+            } else {
+                true
+            }
+        });
+
+        if remembered.is_empty() {
+            return;
+        }
+        info!(
+            "Remembering {} links of node {} until it reappears",
+            remembered.len(),
+            node_id
+        );
+
+        // A node that keeps disappearing must not pile up copies of its links.
+        let mut remembered_links = imp.remembered_links.borrow_mut();
+        for link in remembered {
+            if !remembered_links.contains(&link) {
+                remembered_links.push(link);
+            }
+        }
     }
 
     /// Remove the port with the id `id` from the node with the id `node_id`
@@ -259,13 +1561,81 @@ impl Application {
     fn remove_port(&self, id: u32, node_id: u32) {
         info!("Removing port from graph: id {}, node_id: {}", id, node_id);
 
-        self.imp().graphview.remove_port(id, node_id);
+        let imp = self.imp();
+        imp.graphview.remove_port(id, node_id);
+
+        // Links to the port can not be recreated anymore.
+        imp.link_history
+            .borrow_mut()
+            .retain(|link| link.port_from != id && link.port_to != id);
+    }
+
+    /// Recreate the most recently removed link that does not exist again already.
+    ///
+    /// Links whose ports have disappeared in the meantime are skipped.
+    fn undo_disconnect(&self) {
+        let imp = self.imp();
+        let link = {
+            let mut link_history = imp.link_history.borrow_mut();
+            std::iter::from_fn(|| link_history.pop_front()).find(|link| {
+                let ports_exist = imp
+                    .graphview
+                    .port_names(link.node_from, link.port_from)
+                    .and(imp.graphview.port_names(link.node_to, link.port_to))
+                    .is_some();
+                ports_exist && !imp.graphview.has_link(link.port_from, link.port_to)
+            })
+        };
+
+        match link {
+            Some(link) => {
+                info!(
+                    "Recreating removed link from port {} to port {}",
+                    link.port_from, link.port_to
+                );
+                self.toggle_link(link.port_from, link.port_to);
+            }
+            None => info!("No removed link left to recreate"),
+        }
     }
 
     /// Remove the link with the specified id from the view.
     fn remove_link(&self, id: u32) {
         info!("Removing link from graph: id {}", id);
 
-        self.imp().graphview.remove_link(id);
+        // Number of removed links that are remembered for undoing their removal.
+        const LINK_HISTORY_SIZE: usize = 16;
+
+        let imp = self.imp();
+        let Some(link) = imp.graphview.remove_link(id) else { return };
+        let requested = imp
+            .pending_links
+            .borrow()
+            .contains_key(&(link.port_from, link.port_to));
+        self.clear_pending_link(link.port_from, link.port_to);
+
+        let mut link_history = imp.link_history.borrow_mut();
+        link_history.push_front(link.clone());
+        link_history.truncate(LINK_HISTORY_SIZE);
+        drop(link_history);
+
+        // Links the user removed must not come back with their nodes.
+        if imp.remember_routing.get() && !requested {
+            // The nodes and ports of the link are still on the graph, so their names can be looked up.
+            let named_link = imp
+                .graphview
+                .port_names(link.node_from, link.port_from)
+                .zip(imp.graphview.port_names(link.node_to, link.port_to))
+                .map(|((node_from, port_from), (node_to, port_to))| NamedLink {
+                    node_from,
+                    port_from,
+                    node_to,
+                    port_to,
+                });
+
+            if let Some(named_link) = named_link {
+                imp.removed_links.borrow_mut().push((link, named_link));
+            }
+        }
     }
 }
@@ -0,0 +1,119 @@
+// Copyright 2021 Tom A. Wagner <tom.a.wagner@protonmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License version 3 as published by
+// the Free Software Foundation.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Synthetic graphs for measuring rendering performance.
+//!
+//! When started with `--benchmark N`, this module takes the place of the pipewire thread
+//! and populates the graph with `N` made-up nodes that are randomly linked together.
+
+use gtk::glib;
+use pipewire::spa::Direction;
+
+use crate::{MediaType, NodeType, PipewireMessage};
+
+/// Number of ports created on each side of a node.
+const PORTS_PER_DIRECTION: u32 = 2;
+
+/// The "main" function of the benchmark thread.
+///
+/// Sends messages for `node_count` synthetic nodes, their ports and links between them, then exits.
+pub(super) fn thread_main(gtk_sender: glib::Sender<PipewireMessage>, node_count: u32) {
+    // A fixed seed makes the generated graph the same on every run, so measurements stay comparable.
+    let mut rng = XorShift(0x1234_5678);
+    let mut next_id = 0;
+    let mut new_id = || {
+        next_id += 1;
+        next_id
+    };
+
+    let send = |msg| gtk_sender.send(msg).expect("Failed to send message");
+
+    let mut output_ports = Vec::new();
+    let mut input_ports = Vec::new();
+
+    for i in 0..node_count {
+        let node_id = new_id();
+        let node_type = match i % 3 {
+            0 => Some(NodeType::Output),
+            1 => Some(NodeType::Input),
+            _ => None,
+        };
+
+        send(PipewireMessage::NodeAdded {
+            id: node_id,
+            name: format!("Benchmark Node {}", i),
+            node_name: format!("benchmark_node_{}", i),
+            media_name: None,
+            node_type,
+            device_kind: None,
+            application_name: None,
+        });
+
+        let directions: &[Direction] = match node_type {
+            Some(NodeType::Output) => &[Direction::Output],
+            Some(NodeType::Input) => &[Direction::Input],
+            None => &[Direction::Input, Direction::Output],
+        };
+
+        for direction in directions {
+            for channel in 0..PORTS_PER_DIRECTION {
+                let port_id = new_id();
+                send(PipewireMessage::PortAdded {
+                    id: port_id,
+                    node_id,
+                    name: format!("port_{}", channel),
+                    direction: *direction,
+                    media_type: Some(MediaType::Audio),
+                    index: Some(channel),
+                });
+
+                match direction {
+                    Direction::Output => output_ports.push((node_id, port_id)),
+                    Direction::Input => input_ports.push((node_id, port_id)),
+                }
+            }
+        }
+    }
+
+    if input_ports.is_empty() {
+        return;
+    }
+
+    for (node_from, port_from) in output_ports {
+        let (node_to, port_to) = input_ports[rng.next_u32() as usize % input_ports.len()];
+        send(PipewireMessage::LinkAdded {
+            id: new_id(),
+            node_from,
+            port_from,
+            node_to,
+            port_to,
+            active: rng.next_u32() % 2 == 0,
+            media_type: Some(MediaType::Audio),
+        });
+    }
+}
+
+/// Minimal pseudo-random number generator, good enough for creating random links.
+struct XorShift(u32);
+
+impl XorShift {
+    fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+}
@@ -15,6 +15,8 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 mod application;
+mod benchmark;
+mod patchbay;
 mod pipewire_connection;
 mod view;
 
@@ -27,6 +29,8 @@ use pipewire::spa::Direction;
 enum GtkMessage {
     /// Toggle a link between the two specified ports.
     ToggleLink { port_from: u32, port_to: u32 },
+    /// Make the specified node the default device of its kind.
+    SetDefaultDevice { id: u32, kind: DeviceKind },
     /// Quit the event loop and let the thread finish.
     Terminate,
 }
@@ -37,7 +41,15 @@ enum PipewireMessage {
     NodeAdded {
         id: u32,
         name: String,
+        /// The `node.name` of the node, which identifies it by name, unlike the displayed `name`.
+        node_name: String,
+        /// Name of the media that a stream node carries, e.g. the title of a song being played.
+        media_name: Option<String>,
         node_type: Option<NodeType>,
+        /// The kind of device this node is, if it can be made a default device.
+        device_kind: Option<DeviceKind>,
+        /// Name of the application the node belongs to, used to group the nodes of an application.
+        application_name: Option<String>,
     },
     PortAdded {
         id: u32,
@@ -45,6 +57,8 @@ enum PipewireMessage {
         name: String,
         direction: Direction,
         media_type: Option<MediaType>,
+        /// The ports index on its node, as reported by its `port.id` property.
+        index: Option<u32>,
     },
     LinkAdded {
         id: u32,
@@ -53,11 +67,28 @@ enum PipewireMessage {
         node_to: u32,
         port_to: u32,
         active: bool,
+        /// The type of media the link carries, which is the media type of the node it starts from.
+        media_type: Option<MediaType>,
     },
     LinkStateChanged {
         id: u32,
         active: bool,
     },
+    /// A link requested through [`GtkMessage::ToggleLink`] could not be created.
+    LinkCreationFailed {
+        port_from: u32,
+        port_to: u32,
+    },
+    NodeStateChanged {
+        id: u32,
+        /// Whether the node is currently running, i.e. processing data.
+        active: bool,
+    },
+    DefaultDeviceChanged {
+        /// The id of the new default node, or `None` if no default is set or the node is unknown.
+        id: Option<u32>,
+        kind: DeviceKind,
+    },
     NodeRemoved {
         id: u32,
     },
@@ -70,12 +101,19 @@ enum PipewireMessage {
     },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Copy, Clone)]
 pub enum NodeType {
     Input,
     Output,
 }
 
+/// Kinds of devices that pipewire keeps track of a default for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DeviceKind {
+    Sink,
+    Source,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum MediaType {
     Audio,
@@ -89,6 +127,7 @@ pub struct PipewireLink {
     pub port_from: u32,
     pub node_to: u32,
     pub port_to: u32,
+    pub media_type: Option<MediaType>,
 }
 
 static GLIB_LOGGER: glib::GlibLogger = glib::GlibLogger::new(
@@ -103,6 +142,37 @@ fn init_glib_logger() {
     log::set_max_level(log::LevelFilter::Debug);
 }
 
+/// Get the number of synthetic nodes to create if helvum was started with the hidden `--benchmark N` flag.
+fn benchmark_node_count() -> Option<u32> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--benchmark");
+    args.next()?;
+
+    Some(
+        args.next()
+            .and_then(|count| count.parse().ok())
+            .expect("--benchmark requires the number of nodes to create"),
+    )
+}
+
+/// Apply the size warning thresholds set in the environment, e.g. `HELVUM_NODE_WARNING_THRESHOLD=500`.
+fn apply_warning_thresholds(app: &application::Application) {
+    for (variable, property) in [
+        ("HELVUM_NODE_WARNING_THRESHOLD", "node-warning-threshold"),
+        ("HELVUM_LINK_WARNING_THRESHOLD", "link-warning-threshold"),
+    ] {
+        let Ok(value) = std::env::var(variable) else { continue };
+        match value.parse::<u32>() {
+            Ok(threshold) => app.set_property(property, threshold),
+            Err(_) => log::warn!("Ignoring {}, as {:?} is not a number", variable, value),
+        }
+    }
+}
+
+/// Whether helvum was started with the `--debug` flag, which shows rendering diagnostics on the graph.
+fn debug_requested() -> bool {
+    std::env::args().any(|arg| arg == "--debug")
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     init_glib_logger();
     gtk::init()?;
@@ -114,12 +184,37 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Start the pipewire thread with channels in both directions.
     let (gtk_sender, gtk_receiver) = glib::MainContext::channel(PRIORITY_DEFAULT);
     let (pw_sender, pw_receiver) = pipewire::channel::channel();
+
+    if let Some(node_count) = benchmark_node_count() {
+        // Populate the graph with synthetic nodes instead of connecting to pipewire.
+        let benchmark_thread =
+            std::thread::spawn(move || benchmark::thread_main(gtk_sender, node_count));
+
+        let app = application::Application::new(gtk_receiver, pw_sender);
+        app.report_frame_timings();
+
+        // Arguments are not passed on, as GApplication does not know the benchmark flag.
+        app.run_with_args(&[env!("CARGO_PKG_NAME")]);
+
+        // Keep the receiver alive until here, as the application may send messages to it.
+        drop(pw_receiver);
+        benchmark_thread.join().expect("Benchmark thread panicked");
+
+        return Ok(());
+    }
+
     let pw_thread =
         std::thread::spawn(move || pipewire_connection::thread_main(gtk_sender, pw_receiver));
 
     let app = application::Application::new(gtk_receiver, pw_sender.clone());
-
-    app.run();
+    apply_warning_thresholds(&app);
+    if debug_requested() {
+        app.show_diagnostics();
+    }
+
+    // GApplication does not know the debug flag, so do not pass it on.
+    let args: Vec<String> = std::env::args().filter(|arg| arg != "--debug").collect();
+    app.run_with_args(&args);
 
     pw_sender
         .send(GtkMessage::Terminate)
@@ -0,0 +1,297 @@
+// Copyright 2021 Tom A. Wagner <tom.a.wagner@protonmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License version 3 as published by
+// the Free Software Foundation.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Importing links from the patchbay files of other applications.
+//!
+//! Currently, the XML patchbay files written by qpwgraph are supported.
+//! They look like this, with one `item` for every link:
+//!
+//! ```xml
+//! <patchbay version="0.4.0" name="example">
+//!  <items>
+//!   <item node-type="pipewire" port-type="pipewire-audio">
+//!    <output node="Firefox" port="output_FL"/>
+//!    <input node="alsa_output.pci-0000_00_1f.3.analog-stereo" port="playback_FL"/>
+//!   </item>
+//!  </items>
+//! </patchbay>
+//! ```
+//!
+//! Patchbay files do not contain node positions, and nodes and ports are only identified by their names,
+//! so the links can only be created once nodes and ports with matching names appear on the graph.
+//! Nodes are matched by their `node.name`, not by the name displayed for them.
+
+use log::warn;
+
+/// A link between two ports, identified by the names of the ports and the `node.name`s of their nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedLink {
+    pub node_from: String,
+    pub port_from: String,
+    pub node_to: String,
+    pub port_to: String,
+}
+
+/// Parse the links out of the contents of a qpwgraph patchbay file.
+///
+/// Returns `None` if the contents are not a patchbay file.
+pub fn parse_qpwgraph(contents: &str) -> Option<Vec<NamedLink>> {
+    if element_bodies(contents, "patchbay").is_empty() {
+        return None;
+    }
+
+    let links = element_bodies(contents, "item")
+        .into_iter()
+        .filter_map(|item| {
+            let output = element_bodies(item, "output")
+                .first()
+                .copied()
+                .and_then(endpoint);
+            let input = element_bodies(item, "input")
+                .first()
+                .copied()
+                .and_then(endpoint);
+
+            match (output, input) {
+                (Some((node_from, port_from)), Some((node_to, port_to))) => Some(NamedLink {
+                    node_from,
+                    port_from,
+                    node_to,
+                    port_to,
+                }),
+                _ => {
+                    warn!("Skipping incomplete item in patchbay file");
+                    None
+                }
+            }
+        })
+        .collect();
+
+    Some(links)
+}
+
+/// Get the node and port names of an `output` or `input` element.
+fn endpoint(element: &str) -> Option<(String, String)> {
+    Some((attribute(element, "node")?, attribute(element, "port")?))
+}
+
+/// Get the text following each opening tag of elements with the specified name.
+///
+/// The text reaches up to the closing tag, or up to the end of the opening tag for elements without content.
+fn element_bodies<'a>(text: &'a str, name: &str) -> Vec<&'a str> {
+    let opening = format!("<{}", name);
+    let closing = format!("</{}>", name);
+
+    text.match_indices(&opening)
+        .map(|(index, _)| &text[index + opening.len()..])
+        // Make sure the tag name is not only the start of a longer one, e.g. `items` instead of `item`.
+        .filter(|rest| rest.starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/'))
+        .map(|rest| {
+            let end_of_tag = rest.find('>').map_or(rest.len(), |index| index + 1);
+            if rest[..end_of_tag].ends_with("/>") {
+                &rest[..end_of_tag]
+            } else {
+                rest.find(&closing).map_or(rest, |index| &rest[..index])
+            }
+        })
+        .collect()
+}
+
+/// Get the unescaped value of the attribute with the specified name from the opening tag the text starts with.
+fn attribute(text: &str, name: &str) -> Option<String> {
+    let tag = &text[..text.find('>').unwrap_or(text.len())];
+
+    let mut rest = tag;
+    while let Some(index) = rest.find(name) {
+        let preceded_by_space = rest[..index].ends_with(char::is_whitespace) || index == 0;
+        let after = rest[index + name.len()..].trim_start();
+
+        if preceded_by_space {
+            if let Some(value) = after.strip_prefix('=') {
+                let value = value.trim_start();
+                let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+                let value = &value[1..];
+                let end = value.find(quote)?;
+                return Some(unescape(&value[..end]));
+            }
+        }
+
+        rest = &rest[index + name.len()..];
+    }
+
+    None
+}
+
+/// Replace the predefined XML entities with the characters they stand for.
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(node_from: &str, port_from: &str, node_to: &str, port_to: &str) -> NamedLink {
+        NamedLink {
+            node_from: node_from.to_string(),
+            port_from: port_from.to_string(),
+            node_to: node_to.to_string(),
+            port_to: port_to.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_links_of_items() {
+        let contents = r#"<!DOCTYPE patchbay>
+<patchbay version="0.4.0" name="studio">
+ <items>
+  <item node-type="pipewire" port-type="pipewire-audio">
+   <output node="Firefox" port="output_FL"/>
+   <input node="alsa_output.pci-0000_00_1f.3.analog-stereo" port="playback_FL"/>
+  </item>
+  <item node-type="pipewire" port-type="pipewire-audio">
+   <output node="Firefox" port="output_FR"/>
+   <input node="alsa_output.pci-0000_00_1f.3.analog-stereo" port="playback_FR"/>
+  </item>
+ </items>
+</patchbay>
+"#;
+
+        assert_eq!(
+            parse_qpwgraph(contents),
+            Some(vec![
+                link(
+                    "Firefox",
+                    "output_FL",
+                    "alsa_output.pci-0000_00_1f.3.analog-stereo",
+                    "playback_FL"
+                ),
+                link(
+                    "Firefox",
+                    "output_FR",
+                    "alsa_output.pci-0000_00_1f.3.analog-stereo",
+                    "playback_FR"
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_empty_patchbay() {
+        assert_eq!(
+            parse_qpwgraph(r#"<patchbay version="0.4.0" name="empty"/>"#),
+            Some(vec![])
+        );
+        assert_eq!(
+            parse_qpwgraph("<patchbay><items></items></patchbay>"),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn parses_attribute_variants() {
+        // Single quotes, spaces around `=` and attributes whose names end in the name that is looked for.
+        let contents = r#"<patchbay>
+ <items>
+  <item>
+   <output x-node="wrong" node = 'mpv' port='output_FL'/>
+   <input port="input_1" node="jack_mixer"></input>
+  </item>
+ </items>
+</patchbay>"#;
+
+        assert_eq!(
+            parse_qpwgraph(contents),
+            Some(vec![link("mpv", "output_FL", "jack_mixer", "input_1")])
+        );
+    }
+
+    #[test]
+    fn unescapes_entities() {
+        let contents = r#"<patchbay>
+ <items>
+  <item>
+   <output node="Tom &amp; Jerry &quot;Live&quot;" port="out&lt;1&gt;"/>
+   <input node="it&apos;s" port="&amp;lt;"/>
+  </item>
+ </items>
+</patchbay>"#;
+
+        assert_eq!(
+            parse_qpwgraph(contents),
+            Some(vec![link("Tom & Jerry \"Live\"", "out<1>", "it's", "&lt;")])
+        );
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert_eq!(parse_qpwgraph(""), None);
+        assert_eq!(parse_qpwgraph("not xml at all"), None);
+        assert_eq!(
+            parse_qpwgraph(r#"<patchbays><item></item></patchbays>"#),
+            None
+        );
+    }
+
+    #[test]
+    fn skips_incomplete_items() {
+        let contents = r#"<patchbay>
+ <items>
+  <item>
+   <output node="Firefox" port="output_FL"/>
+  </item>
+  <item>
+   <output node="Firefox"/>
+   <input node="speakers" port="playback_FL"/>
+  </item>
+  <item>
+   <output node="Firefox" port="output_FR/>
+   <input node="speakers" port="playback_FR"/>
+  </item>
+  <item>
+   <output node="mpv" port="output_FL"/>
+   <input node="speakers" port="playback_FL"/>
+  </item>
+ </items>
+</patchbay>"#;
+
+        let links = parse_qpwgraph(contents).unwrap();
+        assert!(links.contains(&link("mpv", "output_FL", "speakers", "playback_FL")));
+        assert!(links.iter().all(|link| link.port_from != "output_FR"));
+    }
+
+    #[test]
+    fn tolerates_missing_closing_tags() {
+        let contents = r#"<patchbay><items><item>
+   <output node="Firefox" port="output_FL"/>
+   <input node="speakers" port="playback_FL"/>"#;
+
+        assert_eq!(
+            parse_qpwgraph(contents),
+            Some(vec![link(
+                "Firefox",
+                "output_FL",
+                "speakers",
+                "playback_FL"
+            )])
+        );
+    }
+}
@@ -22,6 +22,8 @@ use gtk::glib::{self, clone};
 use log::{debug, info, warn};
 use pipewire::{
     link::{Link, LinkChangeMask, LinkListener, LinkState},
+    metadata::{Metadata, MetadataListener},
+    node::{Node, NodeChangeMask, NodeListener, NodeState},
     prelude::*,
     properties,
     registry::{GlobalObject, Registry},
@@ -30,16 +32,27 @@ use pipewire::{
     Context, Core, MainLoop,
 };
 
-use crate::{GtkMessage, MediaType, NodeType, PipewireMessage};
+use crate::{DeviceKind, GtkMessage, MediaType, NodeType, PipewireMessage};
 use state::{Item, State};
 
 enum ProxyItem {
+    Node {
+        _proxy: Node,
+        _listener: NodeListener,
+    },
     Link {
         _proxy: Link,
         _listener: LinkListener,
     },
 }
 
+/// The "default" metadata object, which stores the default devices.
+struct DefaultMetadata {
+    id: u32,
+    proxy: Metadata,
+    _listener: MetadataListener,
+}
+
 /// The "main" function of the pipewire thread.
 pub(super) fn thread_main(
     gtk_sender: glib::Sender<PipewireMessage>,
@@ -53,28 +66,39 @@ pub(super) fn thread_main(
     // Keep proxies and their listeners alive so that we can receive info events.
     let proxies = Rc::new(RefCell::new(HashMap::new()));
 
+    let default_metadata: Rc<RefCell<Option<DefaultMetadata>>> = Rc::new(RefCell::new(None));
+
     let state = Rc::new(RefCell::new(State::new()));
 
     let _receiver = pw_receiver.attach(&mainloop, {
-        clone!(@strong mainloop, @weak core, @weak registry, @strong state => move |msg| match msg {
-            GtkMessage::ToggleLink { port_from, port_to } => toggle_link(port_from, port_to, &core, &registry, &state),
+        clone!(@strong mainloop, @strong gtk_sender, @weak core, @weak registry, @strong default_metadata, @strong state => move |msg| match msg {
+            GtkMessage::ToggleLink { port_from, port_to } => toggle_link(port_from, port_to, &gtk_sender, &core, &registry, &state),
+            GtkMessage::SetDefaultDevice { id, kind } => set_default_device(id, kind, &default_metadata, &state),
             GtkMessage::Terminate => mainloop.quit(),
         })
     });
 
     let _listener = registry
         .add_listener_local()
-        .global(clone!(@strong gtk_sender, @weak registry, @strong proxies, @strong state =>
+        .global(clone!(@strong gtk_sender, @weak registry, @strong proxies, @strong default_metadata, @strong state =>
             move |global| match global.type_ {
-                ObjectType::Node => handle_node(global, &gtk_sender, &state),
+                ObjectType::Node => handle_node(global, &gtk_sender, &registry, &proxies, &state),
                 ObjectType::Port => handle_port(global, &gtk_sender, &state),
                 ObjectType::Link => handle_link(global, &gtk_sender, &registry, &proxies, &state),
+                ObjectType::Metadata => handle_metadata(global, &gtk_sender, &registry, &default_metadata, &state),
                 _ => {
                     // Other objects are not interesting to us
                 }
             }
         ))
-        .global_remove(clone!(@strong proxies, @strong state => move |id| {
+        .global_remove(clone!(@strong proxies, @strong default_metadata, @strong state => move |id| {
+            // The default metadata is not saved in state, as it is not displayed.
+            let mut default_metadata = default_metadata.borrow_mut();
+            if default_metadata.as_ref().map(|metadata| metadata.id) == Some(id) {
+                *default_metadata = None;
+                return;
+            }
+
             if let Some(item) = state.borrow_mut().remove(id) {
                 gtk_sender.send(match item {
                     Item::Node { .. } => PipewireMessage::NodeRemoved {id},
@@ -99,6 +123,8 @@ pub(super) fn thread_main(
 fn handle_node(
     node: &GlobalObject<ForeignDict>,
     sender: &glib::Sender<PipewireMessage>,
+    registry: &Rc<Registry>,
+    proxies: &Rc<RefCell<HashMap<u32, ProxyItem>>>,
     state: &Rc<RefCell<State>>,
 ) {
     let props = node
@@ -115,6 +141,12 @@ fn handle_node(
             .unwrap_or_default(),
     );
 
+    // Streams describe the media they carry separately, which is displayed below the nodes name.
+    let media_name = props
+        .get("media.name")
+        .filter(|media_name| !media_name.is_empty() && *media_name != name)
+        .map(String::from);
+
     // FIXME: Instead of checking these props, the "EnumFormat" parameter should be checked instead.
     let media_type = props.get("media.class").and_then(|class| {
         if class.contains("Audio") {
@@ -149,11 +181,21 @@ fn handle_node(
         })
         .or_else(|| props.get("media.class").and_then(media_class));
 
+    // Only actual devices can be made the default, streams can not.
+    let device_kind = match props.get("media.class") {
+        Some("Audio/Sink") => Some(DeviceKind::Sink),
+        Some("Audio/Source") | Some("Audio/Source/Virtual") => Some(DeviceKind::Source),
+        _ => None,
+    };
+
+    let node_name = String::from(props.get("node.name").unwrap_or_default());
+
     state.borrow_mut().insert(
         node.id,
         Item::Node {
             // widget: node_widget,
             media_type,
+            name: node_name.clone(),
         },
     );
 
@@ -161,9 +203,47 @@ fn handle_node(
         .send(PipewireMessage::NodeAdded {
             id: node.id,
             name,
+            node_name: node_name.clone(),
+            media_name,
             node_type,
+            device_kind,
+            application_name: props.get("application.name").map(String::from),
         })
         .expect("Failed to send message");
+
+    // The default may have been announced before the node appeared.
+    if let Some(kind) = device_kind {
+        if state.borrow().get_default(kind) == Some(node_name.as_str()) {
+            sender
+                .send(PipewireMessage::DefaultDeviceChanged {
+                    id: Some(node.id),
+                    kind,
+                })
+                .expect("Failed to send message");
+        }
+    }
+
+    // Listen for info events, so that we can notify the gtk thread when the node starts or stops running.
+    let proxy: Node = registry.bind(node).expect("Failed to bind to node proxy");
+    let listener = proxy
+        .add_listener_local()
+        .info(clone!(@strong sender => move |info| {
+            if info.change_mask().contains(NodeChangeMask::STATE) {
+                sender.send(PipewireMessage::NodeStateChanged {
+                    id: info.id(),
+                    active: matches!(info.state(), NodeState::Running)
+                }).expect("Failed to send message");
+            }
+        }))
+        .register();
+
+    proxies.borrow_mut().insert(
+        node.id,
+        ProxyItem::Node {
+            _proxy: proxy,
+            _listener: listener,
+        },
+    );
 }
 
 /// Handle a new port being added
@@ -187,6 +267,7 @@ fn handle_port(
     } else {
         Direction::Output
     };
+    let index = props.get("port.id").and_then(|id| id.parse().ok());
 
     // Find out the nodes media type so that the port can be colored.
     let media_type = if let Some(Item::Node { media_type, .. }) = state.borrow().get(node_id) {
@@ -206,6 +287,7 @@ fn handle_port(
             name,
             direction,
             media_type,
+            index,
         })
         .expect("Failed to send message");
 }
@@ -248,6 +330,12 @@ fn handle_link(
                 let node_to = info.input_node_id();
                 let port_to = info.input_port_id();
 
+                // Links carry the same media type as the ports they connect, which is the media type of their nodes.
+                let media_type = match state.get(node_from) {
+                    Some(Item::Node { media_type, .. }) => *media_type,
+                    _ => None,
+                };
+
                 state.insert(id, Item::Link {
                     port_from, port_to
                 });
@@ -258,7 +346,8 @@ fn handle_link(
                     port_from,
                     node_to,
                     port_to,
-                    active: matches!(info.state(), LinkState::Active)
+                    active: matches!(info.state(), LinkState::Active),
+                    media_type,
                 }).expect(
                     "Failed to send message"
                 );
@@ -275,10 +364,112 @@ fn handle_link(
     );
 }
 
+/// Handle a new metadata object being added
+///
+/// Only the "default" metadata is bound, which the session manager uses to store the default devices.
+fn handle_metadata(
+    metadata: &GlobalObject<ForeignDict>,
+    sender: &glib::Sender<PipewireMessage>,
+    registry: &Rc<Registry>,
+    default_metadata: &Rc<RefCell<Option<DefaultMetadata>>>,
+    state: &Rc<RefCell<State>>,
+) {
+    let name = metadata
+        .props
+        .as_ref()
+        .and_then(|props| props.get("metadata.name"));
+    if name != Some("default") {
+        return;
+    }
+
+    debug!(
+        "Default metadata (id:{}) appeared, setting up property listener.",
+        metadata.id
+    );
+
+    let proxy: Metadata = registry
+        .bind(metadata)
+        .expect("Failed to bind to metadata proxy");
+    let listener = proxy
+        .add_listener_local()
+        .property(clone!(@strong sender, @strong state => move |_subject, key, _type, value| {
+            let kind = match key {
+                Some("default.audio.sink") => DeviceKind::Sink,
+                Some("default.audio.source") => DeviceKind::Source,
+                _ => return 0,
+            };
+
+            let name = value.and_then(parse_metadata_name).map(String::from);
+            let id = name.as_deref().and_then(|name| state.borrow().get_node_by_name(name));
+            state.borrow_mut().set_default(kind, name);
+
+            sender.send(PipewireMessage::DefaultDeviceChanged { id, kind }).expect("Failed to send message");
+
+            0
+        }))
+        .register();
+
+    *default_metadata.borrow_mut() = Some(DefaultMetadata {
+        id: metadata.id,
+        proxy,
+        _listener: listener,
+    });
+}
+
+/// Extract the node name from a metadata value like `{ "name": "alsa_output.pci-0000_00_1f.3.analog-stereo" }`.
+fn parse_metadata_name(value: &str) -> Option<&str> {
+    let (_, rest) = value.split_once("\"name\"")?;
+    let (_, rest) = rest.split_once(':')?;
+    let (_, rest) = rest.split_once('"')?;
+    let (name, _) = rest.split_once('"')?;
+
+    Some(name)
+}
+
+/// Make the node with the specified id the default device of the specified kind.
+fn set_default_device(
+    id: u32,
+    kind: DeviceKind,
+    default_metadata: &Rc<RefCell<Option<DefaultMetadata>>>,
+    state: &Rc<RefCell<State>>,
+) {
+    let default_metadata = default_metadata.borrow();
+    let Some(default_metadata) = default_metadata.as_ref() else {
+        warn!("Can not set default device, as no default metadata exists");
+        return;
+    };
+
+    let state = state.borrow();
+    let Some(Item::Node { name, .. }) = state.get(id) else {
+        warn!("Requested default device (id:{}) is not a known node", id);
+        return;
+    };
+
+    info!(
+        "Requesting node with id {} to be the default {:?}",
+        id, kind
+    );
+
+    // The "configured" key is the users choice, which the session manager saves and applies.
+    let key = match kind {
+        DeviceKind::Sink => "default.configured.audio.sink",
+        DeviceKind::Source => "default.configured.audio.source",
+    };
+    let value = format!(
+        "{{ \"name\": \"{}\" }}",
+        name.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+
+    default_metadata
+        .proxy
+        .set_property(0, key, Some("Spa:String:JSON"), Some(&value));
+}
+
 /// Toggle a link between the two specified ports.
 fn toggle_link(
     port_from: u32,
     port_to: u32,
+    sender: &glib::Sender<PipewireMessage>,
     core: &Rc<Core>,
     registry: &Rc<Registry>,
     state: &Rc<RefCell<State>>,
@@ -295,12 +486,21 @@ fn toggle_link(
             port_from, port_to
         );
 
-        let node_from = state
-            .get_node_of_port(port_from)
-            .expect("Requested port not in state");
-        let node_to = state
-            .get_node_of_port(port_to)
-            .expect("Requested port not in state");
+        // A port may have disappeared since the link was requested, e.g. when undoing the removal of a link.
+        let (Some(node_from), Some(node_to)) = (
+            state.get_node_of_port(port_from),
+            state.get_node_of_port(port_to),
+        ) else {
+            warn!(
+                "Can not link port id:{} to port id:{}, as one of them does not exist anymore",
+                port_from, port_to
+            );
+
+            sender
+                .send(PipewireMessage::LinkCreationFailed { port_from, port_to })
+                .expect("Failed to send message");
+            return;
+        };
 
         if let Err(e) = core.create_object::<Link, _>(
             "link-factory",
@@ -313,6 +513,10 @@ fn toggle_link(
             },
         ) {
             warn!("Failed to create link: {}", e);
+
+            sender
+                .send(PipewireMessage::LinkCreationFailed { port_from, port_to })
+                .expect("Failed to send message");
         }
     }
 }
@@ -16,7 +16,7 @@
 
 use std::collections::HashMap;
 
-use crate::MediaType;
+use crate::{DeviceKind, MediaType};
 
 /// Any pipewire item we need to keep track of.
 /// These will be saved in the `State` struct associated with their id.
@@ -24,6 +24,8 @@ pub(super) enum Item {
     Node {
         // Keep track of the nodes media type to color ports on it.
         media_type: Option<MediaType>,
+        // The `node.name` property, which is used to refer to the node in the "default" metadata.
+        name: String,
     },
     Port {
         // Save the id of the node this is on so we can remove the port from it
@@ -45,6 +47,8 @@ pub(super) struct State {
     items: HashMap<u32, Item>,
     /// Map `(output port id, input port id)` tuples to the id of the link that connects them.
     links: HashMap<(u32, u32), u32>,
+    /// Map device kinds to the `node.name` of the current default device of that kind.
+    defaults: HashMap<DeviceKind, String>,
 }
 
 impl State {
@@ -94,4 +98,27 @@ impl State {
             None
         }
     }
+
+    /// Get the id of the node with the specified `node.name` property.
+    pub fn get_node_by_name(&self, name: &str) -> Option<u32> {
+        self.items.iter().find_map(|(id, item)| match item {
+            Item::Node {
+                name: node_name, ..
+            } if node_name == name => Some(*id),
+            _ => None,
+        })
+    }
+
+    /// Get the `node.name` of the current default device of the specified kind.
+    pub fn get_default(&self, kind: DeviceKind) -> Option<&str> {
+        self.defaults.get(&kind).map(String::as_str)
+    }
+
+    /// Set the `node.name` of the current default device of the specified kind.
+    pub fn set_default(&mut self, kind: DeviceKind, name: Option<String>) {
+        match name {
+            Some(name) => self.defaults.insert(kind, name),
+            None => self.defaults.remove(&kind),
+        };
+    }
 }
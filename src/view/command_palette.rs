@@ -0,0 +1,206 @@
+// Copyright 2021 Tom A. Wagner <tom.a.wagner@protonmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License version 3 as published by
+// the Free Software Foundation.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+use log::warn;
+
+/// An action that can be run from the command palette.
+#[derive(Debug, Clone)]
+pub struct Command {
+    /// Human readable name of the command.
+    pub label: String,
+    /// Detailed name of the action, e.g. `win.orientation::vertical`.
+    pub action: String,
+    /// Human readable accelerator of the action, if it has one.
+    pub accelerator: Option<String>,
+}
+
+mod imp {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    use once_cell::unsync::OnceCell;
+
+    #[derive(Default)]
+    pub struct CommandPalette {
+        pub(super) search_entry: gtk::SearchEntry,
+        pub(super) list: gtk::ListBox,
+        pub(super) commands: RefCell<Vec<Command>>,
+        /// The window whose actions are run.
+        pub(super) target: OnceCell<gtk::Window>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for CommandPalette {
+        const NAME: &'static str = "HelvumCommandPalette";
+        type Type = super::CommandPalette;
+        type ParentType = gtk::Window;
+    }
+
+    impl ObjectImpl for CommandPalette {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.set_title(Some("Commands"));
+            obj.set_modal(true);
+            obj.set_default_size(420, 360);
+
+            self.search_entry
+                .set_placeholder_text(Some("Search commands…"));
+            self.list.set_selection_mode(gtk::SelectionMode::Browse);
+            self.list.set_activate_on_single_click(true);
+
+            let scrolled_window = gtk::ScrolledWindow::builder()
+                .child(&self.list)
+                .vexpand(true)
+                .hscrollbar_policy(gtk::PolicyType::Never)
+                .build();
+
+            let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+            content.append(&self.search_entry);
+            content.append(&scrolled_window);
+            obj.set_child(Some(&content));
+
+            self.list
+                .set_filter_func(glib::clone!(@weak obj => @default-return true, move |row| {
+                    obj.imp().matches_search(row.index())
+                }));
+
+            self.search_entry
+                .connect_search_changed(glib::clone!(@weak obj => move |_| {
+                    obj.imp().list.invalidate_filter();
+                    obj.imp().select_first_visible();
+                }));
+            // Enter runs the selected command.
+            self.search_entry
+                .connect_activate(glib::clone!(@weak obj => move |_| {
+                    if let Some(row) = obj.imp().list.selected_row() {
+                        obj.imp().run(row.index());
+                    }
+                }));
+            // Escape closes the palette.
+            self.search_entry
+                .connect_stop_search(glib::clone!(@weak obj => move |_| obj.close()));
+            self.search_entry.set_key_capture_widget(Some(&*obj));
+
+            self.list
+                .connect_row_activated(glib::clone!(@weak obj => move |_, row| {
+                    obj.imp().run(row.index());
+                }));
+        }
+    }
+    impl WidgetImpl for CommandPalette {}
+    impl WindowImpl for CommandPalette {}
+
+    impl CommandPalette {
+        /// Whether the command at the specified index matches the current search text.
+        fn matches_search(&self, index: i32) -> bool {
+            let search = self.search_entry.text().to_lowercase();
+            let commands = self.commands.borrow();
+            let Some(command) = usize::try_from(index)
+                .ok()
+                .and_then(|index| commands.get(index))
+            else {
+                return false;
+            };
+
+            search
+                .split_whitespace()
+                .all(|word| command.label.to_lowercase().contains(word))
+        }
+
+        fn select_first_visible(&self) {
+            let mut child = self.list.first_child();
+            while let Some(widget) = child {
+                if let Some(row) = widget.downcast_ref::<gtk::ListBoxRow>() {
+                    if row.is_child_visible() {
+                        self.list.select_row(Some(row));
+                        return;
+                    }
+                }
+                child = widget.next_sibling();
+            }
+            self.list.select_row(gtk::ListBoxRow::NONE);
+        }
+
+        /// Close the palette and run the command at the specified index on the target window.
+        fn run(&self, index: i32) {
+            let obj = self.obj();
+            let Some(command) = usize::try_from(index)
+                .ok()
+                .and_then(|index| self.commands.borrow().get(index).cloned())
+            else {
+                return;
+            };
+            obj.close();
+
+            let Some(target) = self.target.get() else { return };
+            match gio::Action::parse_detailed_name(&command.action) {
+                Ok((name, parameter)) => {
+                    if let Err(e) = target.activate_action(&name, parameter.as_ref()) {
+                        warn!("Failed to run command {}: {}", command.action, e);
+                    }
+                }
+                Err(e) => warn!("Invalid action name {}: {}", command.action, e),
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A searchable list of commands, which runs the chosen command on its parent window.
+    pub struct CommandPalette(ObjectSubclass<imp::CommandPalette>)
+        @extends gtk::Window, gtk::Widget;
+}
+
+impl CommandPalette {
+    pub fn new(parent: &impl IsA<gtk::Window>, commands: Vec<Command>) -> Self {
+        let palette: Self = glib::Object::builder()
+            .property("transient-for", parent)
+            .build();
+
+        let imp = palette.imp();
+        for command in &commands {
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+            row.set_margin_top(6);
+            row.set_margin_bottom(6);
+            row.set_margin_start(12);
+            row.set_margin_end(12);
+
+            let label = gtk::Label::new(Some(&command.label));
+            label.set_xalign(0.0);
+            label.set_hexpand(true);
+            row.append(&label);
+
+            if let Some(ref accelerator) = command.accelerator {
+                let accelerator = gtk::Label::new(Some(accelerator));
+                accelerator.add_css_class("dim-label");
+                row.append(&accelerator);
+            }
+
+            imp.list.append(&row);
+        }
+        *imp.commands.borrow_mut() = commands;
+        imp.target
+            .set(parent.clone().upcast())
+            .expect("Command palette target already set");
+        imp.select_first_visible();
+
+        palette
+    }
+}
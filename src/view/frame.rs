@@ -0,0 +1,159 @@
+// Copyright 2021 Tom A. Wagner <tom.a.wagner@protonmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License version 3 as published by
+// the Free Software Foundation.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use gtk::{gdk, gio, glib, prelude::*, subclass::prelude::*};
+
+mod imp {
+    use glib::subclass::Signal;
+    use once_cell::sync::Lazy;
+
+    use super::*;
+
+    pub struct Frame {
+        pub(super) content: gtk::Box,
+        pub(super) title: gtk::EditableLabel,
+        /// Grip in the bottom right corner, which can be dragged to resize the frame.
+        pub(super) resize_handle: gtk::Image,
+        pub(super) context_menu: gtk::PopoverMenu,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Frame {
+        const NAME: &'static str = "HelvumFrame";
+        type Type = super::Frame;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+            klass.set_css_name("groupframe");
+
+            klass.install_action("frame.remove", None, |frame, _, _| {
+                frame.emit_by_name::<()>("remove-requested", &[]);
+            });
+        }
+
+        fn new() -> Self {
+            let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+
+            let title = gtk::EditableLabel::new("");
+            title.set_halign(gtk::Align::Start);
+            content.append(&title);
+
+            let resize_handle = gtk::Image::from_icon_name("list-drag-handle-symbolic");
+            resize_handle.set_halign(gtk::Align::End);
+            resize_handle.set_valign(gtk::Align::End);
+            resize_handle.set_vexpand(true);
+            resize_handle.set_cursor(gdk::Cursor::from_name("se-resize", None).as_ref());
+            content.append(&resize_handle);
+
+            let menu = gio::Menu::new();
+            menu.append(Some("Remove Frame"), Some("frame.remove"));
+            let context_menu = gtk::PopoverMenu::from_model(Some(&menu));
+            context_menu.set_has_arrow(false);
+
+            Self {
+                content,
+                title,
+                resize_handle,
+                context_menu,
+            }
+        }
+    }
+
+    impl ObjectImpl for Frame {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            self.content.set_parent(&*obj);
+            self.context_menu.set_parent(&*obj);
+
+            // Display a grab cursor when the mouse is over the frame so the user knows it can be dragged.
+            obj.set_cursor(gdk::Cursor::from_name("grab", None).as_ref());
+
+            let click_controller = gtk::GestureClick::new();
+            click_controller.set_button(gdk::BUTTON_SECONDARY);
+            click_controller.connect_pressed(|click_controller, _, x, y| {
+                let frame = click_controller
+                    .widget()
+                    .downcast::<super::Frame>()
+                    .unwrap();
+
+                let context_menu = &frame.imp().context_menu;
+                context_menu.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+                context_menu.popup();
+            });
+            obj.add_controller(click_controller);
+        }
+
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> =
+                Lazy::new(|| vec![glib::ParamSpecString::builder("title").build()]);
+
+            PROPERTIES.as_ref()
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "title" => self.title.text().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            match pspec.name() {
+                "title" => self.title.set_text(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> =
+                Lazy::new(|| vec![Signal::builder("remove-requested").build()]);
+
+            SIGNALS.as_ref()
+        }
+
+        fn dispose(&self) {
+            self.content.unparent();
+            self.context_menu.unparent();
+        }
+    }
+
+    impl WidgetImpl for Frame {
+        fn size_allocate(&self, width: i32, height: i32, baseline: i32) {
+            self.parent_size_allocate(width, height, baseline);
+            self.context_menu.present();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A labeled rectangle drawn behind nodes on the canvas, which users can use to group nodes spatially.
+    pub struct Frame(ObjectSubclass<imp::Frame>)
+        @extends gtk::Widget;
+}
+
+impl Frame {
+    pub fn new(title: &str) -> Self {
+        glib::Object::builder().property("title", &title).build()
+    }
+
+    /// Check whether the widget is the grip used for resizing the frame.
+    pub fn is_resize_handle(&self, widget: &gtk::Widget) -> bool {
+        widget == self.imp().resize_handle.upcast_ref::<gtk::Widget>()
+    }
+}
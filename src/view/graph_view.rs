@@ -14,9 +14,10 @@
 //
 // SPDX-License-Identifier: GPL-3.0-only
 
-use super::{Node, Port};
+use super::{Frame, Node, Note, Port};
 
 use gtk::{
+    gio,
     glib::{self, clone},
     graphene,
     graphene::Point,
@@ -25,25 +26,217 @@ use gtk::{
     subclass::prelude::*,
 };
 use log::{error, warn};
+use pipewire::spa::Direction;
 
-use std::{cmp::Ordering, collections::HashMap};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+};
 
-use crate::NodeType;
+use crate::{DeviceKind, MediaType, NodeType};
 
 const CANVAS_SIZE: f64 = 5000.0;
 
+/// Edge length of the square minimap, in pixels.
+const MINIMAP_SIZE: f32 = 160.0;
+/// Distance of the minimap to the bottom right corner of the view, in pixels.
+const MINIMAP_MARGIN: f32 = 12.0;
+
+/// Space between the border of an application group and its nodes, in canvas space.
+const GROUP_PADDING: f32 = 12.0;
+/// Height of the title of an application group, in canvas space.
+const GROUP_HEADER_HEIGHT: f32 = 24.0;
+/// Width of a collapsed application group, which only shows its title, in canvas space.
+const COLLAPSED_GROUP_WIDTH: f32 = 180.0;
+
+/// Kinds of nodes that can be highlighted on the graph, to make them stand out in a busy graph.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "HelvumNodeHighlight")]
+pub enum NodeHighlight {
+    #[default]
+    None,
+    /// Nodes that consume data, like speakers.
+    Sinks,
+    /// Nodes that produce data, like microphones.
+    Sources,
+    /// Audio devices that can be made the default device.
+    Devices,
+}
+
+/// Which links are drawn, depending on whether they are active.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "HelvumLinkFilter")]
+pub enum LinkFilter {
+    #[default]
+    All,
+    /// Only links that currently carry data.
+    Active,
+    /// Only links that are not currently carrying data, which are drawn dashed.
+    Inactive,
+}
+
+impl LinkFilter {
+    /// Whether a link with the specified state passes the filter.
+    fn matches(self, active: bool) -> bool {
+        match self {
+            LinkFilter::All => true,
+            LinkFilter::Active => active,
+            LinkFilter::Inactive => !active,
+        }
+    }
+}
+
+/// How links that are not currently carrying data are drawn.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "HelvumInactiveLinkStyle")]
+pub enum InactiveLinkStyle {
+    /// Draw inactive links with a dashed line.
+    #[default]
+    Dashed,
+    /// Draw inactive links with a full, but faded line.
+    Dimmed,
+    /// Do not draw inactive links at all.
+    Hidden,
+}
+
+impl InactiveLinkStyle {
+    /// Get the style that follows this one, wrapping around after the last one.
+    pub fn next(self) -> Self {
+        match self {
+            InactiveLinkStyle::Dashed => InactiveLinkStyle::Dimmed,
+            InactiveLinkStyle::Dimmed => InactiveLinkStyle::Hidden,
+            InactiveLinkStyle::Hidden => InactiveLinkStyle::Dashed,
+        }
+    }
+}
+
+/// The shape links are drawn with.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "HelvumLinkStyle")]
+pub enum LinkStyle {
+    /// Draw links as S-shaped curves.
+    #[default]
+    Bezier,
+    /// Draw links as straight lines.
+    Straight,
+    /// Draw links with horizontal and vertical segments, meeting at right angles.
+    Orthogonal,
+}
+
+/// Take the position that was requested for a node with the specified name before it appeared on the graph,
+/// or use the default position if there is none.
+///
+/// A requested position is only used once, later nodes with the same name get the default position again.
+fn take_initial_position(
+    pending_positions: &mut HashMap<String, Point>,
+    name: &str,
+    default: impl FnOnce() -> Point,
+) -> Point {
+    pending_positions.remove(name).unwrap_or_else(default)
+}
+
+/// Radius of the loop drawn for links between ports that are very close together,
+/// during neutral zoom (factor 1.0).
+const NORMAL_LOOP_RADIUS: f64 = 10.0;
+
+/// Get the center and radius of the loop that is drawn for a link from `from` to `to` instead of a curve,
+/// or `None` if its ends are far enough apart for a curve.
+///
+/// Coordinates are in flow space, where signals flow from left to right.
+/// The loop sits on top of the middle between both ends, so it passes right through them.
+fn link_loop(from: (f64, f64), to: (f64, f64), zoom_factor: f64) -> Option<((f64, f64), f64)> {
+    let radius = NORMAL_LOOP_RADIUS * zoom_factor;
+    if f64::hypot(to.0 - from.0, to.1 - from.1) >= radius {
+        return None;
+    }
+
+    let (mid_x, mid_y) = ((from.0 + to.0) / 2.0, (from.1 + to.1) / 2.0);
+    Some(((mid_x, mid_y - radius), radius))
+}
+
+/// Gap between nodes packed by [`pack_in_columns`], in canvas space.
+const PACKING_SPACING: f32 = 20.0;
+
+/// Get the top left corners of rectangles with the specified sizes packed into columns centered on `center`,
+/// so that none of them overlap.
+///
+/// Like new nodes, which are stacked in columns growing down, the rectangles fill one column after another,
+/// in the order they are specified in. The grid gets about as many columns as rows.
+fn pack_in_columns(sizes: &[(f32, f32)], center: Point) -> Vec<Point> {
+    if sizes.is_empty() {
+        return Vec::new();
+    }
+
+    let columns = (sizes.len() as f64).sqrt().ceil() as usize;
+    let rows = (sizes.len() + columns - 1) / columns;
+
+    let mut positions = Vec::with_capacity(sizes.len());
+    let (mut x, mut height) = (0.0, 0.0_f32);
+    for column in sizes.chunks(rows) {
+        let mut y = 0.0;
+        for (_, item_height) in column {
+            positions.push((x, y));
+            y += item_height + PACKING_SPACING;
+        }
+        height = height.max(y - PACKING_SPACING);
+
+        let width = column.iter().map(|(width, _)| *width).fold(0.0, f32::max);
+        x += width + PACKING_SPACING;
+    }
+    let width = x - PACKING_SPACING;
+
+    let (left, top) = (center.x() - width / 2.0, center.y() - height / 2.0);
+    positions
+        .into_iter()
+        .map(|(x, y)| Point::new(left + x, top + y))
+        .collect()
+}
+
 mod imp {
     use super::*;
 
-    use std::cell::{Cell, RefCell};
+    use std::{
+        cell::{Cell, RefCell},
+        time::{Duration, Instant},
+    };
 
+    use glib::subclass::Signal;
     use gtk::{
         gdk::{self, RGBA},
         graphene::Rect,
         gsk::ColorStop,
     };
     use log::warn;
-    use once_cell::sync::Lazy;
+    use once_cell::{sync::Lazy, unsync::OnceCell};
+
+    /// This keeps track of an ongoing frame drag operation, which either moves or resizes the frame.
+    pub struct FrameDragState {
+        frame: glib::WeakRef<Frame>,
+        /// The frames rectangle in canvas space when the drag started.
+        start_rect: Rect,
+        /// Whether the frame is being resized instead of moved.
+        resizing: bool,
+        /// Nodes that were inside the frame when the drag started, with their positions at that time.
+        ///
+        /// These are moved together with the frame.
+        nodes: Vec<(glib::WeakRef<Node>, Point)>,
+    }
+
+    /// This keeps track of an ongoing note drag operation.
+    pub struct NoteDragState {
+        note: glib::WeakRef<Note>,
+        /// The offset of the pointer to the origin of the note in canvas space, like [`DragState::offset`].
+        offset: Point,
+    }
+
+    /// This keeps track of a link whose end is dragged to another port.
+    pub struct RerouteState {
+        link: crate::PipewireLink,
+        /// Whether the input end of the link is moved, instead of the output end.
+        moving_input: bool,
+        /// The position of the pointer in screen space, where the moved end is drawn.
+        pointer: (f64, f64),
+    }
 
     pub struct DragState {
         node: glib::WeakRef<Node>,
@@ -52,6 +245,32 @@ mod imp {
         ///
         /// The offset is normalized to the default zoom-level of 1.0.
         offset: Point,
+        /// The position of the node in canvas space when the drag started.
+        start: Point,
+        /// Other selected nodes that are moved along with the dragged node, with their positions when the drag started.
+        selected: Vec<(glib::WeakRef<Node>, Point)>,
+    }
+
+    /// This keeps track of an ongoing zoom animation, see [`super::GraphView::animate_zoom_factor`].
+    pub struct ZoomAnimation {
+        /// The zoom factor when the animation started.
+        pub(super) start_zoom: f64,
+        pub(super) target_zoom: f64,
+        /// The point in screen space that stays in place while zooming.
+        pub(super) anchor: Option<(f64, f64)>,
+        /// The frame time when the animation started, in microseconds. Set on the first frame.
+        pub(super) start_time: Option<i64>,
+        pub(super) tick: gtk::TickCallbackId,
+    }
+
+    /// This keeps track of an ongoing scroll animation, see [`super::GraphView::center_on_node`].
+    pub struct ScrollAnimation {
+        /// The adjustment values when the animation started.
+        pub(super) start: (f64, f64),
+        pub(super) target: (f64, f64),
+        /// The frame time when the animation started, in microseconds. Set on the first frame.
+        pub(super) start_time: Option<i64>,
+        pub(super) tick: gtk::TickCallbackId,
     }
 
     #[derive(Default)]
@@ -60,14 +279,126 @@ mod imp {
         pub(super) nodes: RefCell<HashMap<u32, (Node, Point)>>,
         /// Stores the link and whether it is currently active.
         pub(super) links: RefCell<HashMap<u32, (crate::PipewireLink, bool)>>,
+        /// Positions of nodes that were requested by `node.name` before the node appeared on the graph.
+        ///
+        /// These are applied instead of the default position once a node with a matching name is added.
+        /// Nodes that are removed leave their position here, so that they reappear where they were.
+        pub(super) pending_positions: RefCell<HashMap<String, Point>>,
+        /// Names of the nodes the user marked as favorites, including those not currently on the graph.
+        pub(super) favorites: RefCell<BTreeSet<String>>,
+        /// The lowercase text of the current node search, empty if no search is active.
+        pub(super) search_query: RefCell<String>,
         pub hadjustment: RefCell<Option<gtk::Adjustment>>,
         pub vadjustment: RefCell<Option<gtk::Adjustment>>,
         pub zoom_factor: Cell<f64>,
         /// This keeps track of an ongoing node drag operation.
         pub dragged_node: RefCell<Option<DragState>>,
+        /// Frames drawn behind the nodes and their rectangles in canvas space.
+        pub(super) frames: RefCell<Vec<(Frame, Rect)>>,
+        pub dragged_frame: RefCell<Option<FrameDragState>>,
+        /// Notes placed on the canvas and their positions in canvas space.
+        pub(super) notes: RefCell<Vec<(Note, Point)>>,
+        pub dragged_note: RefCell<Option<NoteDragState>>,
         // Memorized data for an in-progress zoom gesture
         pub zoom_gesture_initial_zoom: Cell<Option<f64>>,
         pub zoom_gesture_anchor: Cell<Option<(f64, f64)>>,
+        /// Whether a connector dot is drawn where a link attaches to a port.
+        pub show_link_endpoints: Cell<bool>,
+        /// Whether the channel a link carries is written next to its middle.
+        pub show_link_labels: Cell<bool>,
+        pub show_grid: Cell<bool>,
+        /// Distance between grid lines during neutral zoom (factor 1.0).
+        pub grid_size: Cell<f32>,
+        /// Scales how far the control points of link curves stick out from the ports.
+        pub link_tension: Cell<f32>,
+        /// Whether nodes that are not running are drawn dimmed.
+        pub dim_inactive_nodes: Cell<bool>,
+        /// Opacity of dimmed nodes that are not running.
+        pub inactive_node_opacity: Cell<f64>,
+        /// Style of dimmed nodes, which applies the opacity through CSS so that it keeps its transition.
+        pub(super) dimming_style: gtk::CssProvider,
+        /// Whether nodes that produce data are shown.
+        pub show_sources: Cell<bool>,
+        /// Whether nodes that consume data are shown.
+        pub show_sinks: Cell<bool>,
+        /// Whether nodes that are neither sources nor sinks, such as filters, are shown.
+        pub show_other_nodes: Cell<bool>,
+        /// Whether scrolling past one edge of the canvas continues from the opposite edge.
+        pub wrap_scrolling: Cell<bool>,
+        /// Whether the background is tinted depending on how many nodes and links are in an area.
+        pub show_link_density: Cell<bool>,
+        /// Whether ports show their index next to their name.
+        pub show_port_indices: Cell<bool>,
+        /// How far outside the visible area, in pixels, children and links are still drawn.
+        pub culling_margin: Cell<i32>,
+        /// Whether the zoom factor is kept fixed, while still allowing the view to be panned.
+        pub zoom_locked: Cell<bool>,
+        /// Whether scrolling up zooms out instead of in.
+        pub invert_zoom_scroll: Cell<bool>,
+        /// Whether zooming with the scroll wheel or a gesture smoothly animates to the new zoom factor.
+        pub animate_zoom: Cell<bool>,
+        pub(super) zoom_animation: RefCell<Option<ZoomAnimation>>,
+        pub(super) scroll_animation: RefCell<Option<ScrollAnimation>>,
+        /// Whether links are drawn above nodes instead of being tucked under them.
+        pub links_on_top: Cell<bool>,
+        /// Whether nodes of the same application are drawn inside a common box.
+        pub group_by_application: Cell<bool>,
+        /// Names of the applications whose group is collapsed, hiding its nodes.
+        pub(super) collapsed_groups: RefCell<HashSet<String>>,
+        /// Rectangles of the collapsed application groups in canvas space, by application name.
+        ///
+        /// Updated on each draw, so links of hidden nodes do not need to look up their group.
+        pub(super) collapsed_group_rects: RefCell<HashMap<String, Rect>>,
+        /// The link whose end is currently dragged to another port.
+        pub(super) rerouted_link: RefCell<Option<RerouteState>>,
+        /// Whether signals flow from top to bottom instead of from left to right.
+        pub vertical_flow: Cell<bool>,
+        /// Scales how strongly pinch gestures change the zoom factor.
+        pub zoom_gesture_sensitivity: Cell<f64>,
+        /// Which kind of nodes are highlighted.
+        pub node_highlight: Cell<NodeHighlight>,
+        /// Which links are drawn, depending on whether they are active.
+        pub link_filter: Cell<LinkFilter>,
+        pub inactive_link_style: Cell<InactiveLinkStyle>,
+        pub link_style: Cell<LinkStyle>,
+        /// The node under the pointer, whose links are highlighted by dimming all other links.
+        pub hovered_node: Cell<Option<u32>>,
+        /// The port that was clicked, whose links are the only ones drawn until the focus is cleared.
+        pub focused_port: Cell<Option<u32>>,
+        /// The focused port and the ports connected to it, which are styled to stand out.
+        pub(super) focus_styled_ports: RefCell<HashSet<u32>>,
+        /// Values of the adjustments when panning with the middle mouse button started.
+        pub pan_start: Cell<Option<(f64, f64)>>,
+        /// The port a link is being dragged out of, and the pointer position in screen space.
+        pub link_preview: Cell<Option<(u32, (f64, f64))>>,
+        /// Zoom factor below which nodes are drawn as simple blocks without their ports.
+        pub lod_threshold: Cell<f64>,
+        /// Whether an overview of the whole canvas is shown in a corner of the view.
+        pub show_minimap: Cell<bool>,
+        /// Whether the frame rate, rendering time and size of the graph are shown in a corner of the view.
+        pub show_diagnostics: Cell<bool>,
+        /// Queues a single reallocation on the next frame after nodes were moved, see [`super::GraphView::move_node`].
+        pub(super) allocation_tick: RefCell<Option<gtk::TickCallbackId>>,
+        /// Redraws the view every frame while diagnostics are shown, so that the frame rate can be measured.
+        pub(super) diagnostics_tick: RefCell<Option<gtk::TickCallbackId>>,
+        /// Frame rate reported by the frame clock.
+        pub(super) diagnostics_fps: Cell<f64>,
+        /// How long building the last snapshot took.
+        pub(super) diagnostics_snapshot_time: Cell<Duration>,
+        /// Whether the current drag started on the minimap, which moves the view instead of the canvas contents.
+        pub minimap_dragging: Cell<bool>,
+        /// Ids of the selected nodes, which are moved together.
+        pub(super) selected_nodes: RefCell<HashSet<u32>>,
+        /// The rectangle that is being dragged out on empty canvas to select nodes, in screen space.
+        pub rubberband: Cell<Option<Rect>>,
+        /// Context menu shown when right-clicking an empty part of the canvas.
+        pub canvas_menu: OnceCell<gtk::PopoverMenu>,
+        /// Context menu shown when right-clicking a link.
+        pub link_menu: OnceCell<gtk::PopoverMenu>,
+        /// The link that the link menu was last opened for.
+        pub menu_link: Cell<Option<u32>>,
+        /// Where the last context menu was opened, in screen space.
+        pub menu_position: Cell<(f64, f64)>,
     }
 
     #[glib::object_subclass]
@@ -79,6 +410,18 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.set_css_name("graphview");
+
+            klass.install_action("graph.copy-link-info", None, |widget, _, _| {
+                widget.imp().copy_link_info();
+            });
+            klass.install_action("graph.add-frame", None, |widget, _, _| {
+                let (x, y) = widget.imp().menu_position.get();
+                widget.add_frame_at(x, y);
+            });
+            klass.install_action("graph.add-note", None, |widget, _, _| {
+                let (x, y) = widget.imp().menu_position.get();
+                widget.add_note_at(x, y);
+            });
         }
     }
 
@@ -91,13 +434,65 @@ mod imp {
             self.setup_node_dragging();
             self.setup_scroll_zooming();
             self.setup_zoom_gesture();
+            self.setup_canvas_menu();
+            self.setup_node_hovering();
+            self.setup_link_preview();
+            self.setup_panning();
+            self.setup_keyboard_navigation();
         }
 
         fn dispose(&self) {
             self.nodes
                 .borrow()
                 .values()
-                .for_each(|(node, _)| node.unparent())
+                .for_each(|(node, _)| node.unparent());
+            self.frames
+                .borrow()
+                .iter()
+                .for_each(|(frame, _)| frame.unparent());
+            self.notes
+                .borrow()
+                .iter()
+                .for_each(|(note, _)| note.unparent());
+
+            if let Some(canvas_menu) = self.canvas_menu.get() {
+                canvas_menu.unparent();
+            }
+            if let Some(link_menu) = self.link_menu.get() {
+                link_menu.unparent();
+            }
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+                vec![
+                    Signal::builder("link-remove-requested")
+                        // Provide id of output port and input port of the link to the signal handler.
+                        .param_types([<u32>::static_type(), <u32>::static_type()])
+                        .build(),
+                    Signal::builder("link-reroute-requested")
+                        // Provide the output and input port ids of the old link, then those of the new link.
+                        .param_types([
+                            <u32>::static_type(),
+                            <u32>::static_type(),
+                            <u32>::static_type(),
+                            <u32>::static_type(),
+                        ])
+                        .build(),
+                    Signal::builder("node-moved")
+                        // Provide id of the node and its new position in canvas space to the signal handler.
+                        .param_types([
+                            <u32>::static_type(),
+                            <f64>::static_type(),
+                            <f64>::static_type(),
+                        ])
+                        .build(),
+                    // Emitted whenever a node or link is added to or removed from the graph.
+                    Signal::builder("graph-changed").build(),
+                ]
+            });
+
+            SIGNALS.as_ref()
         }
 
         fn properties() -> &'static [glib::ParamSpec] {
@@ -113,6 +508,129 @@ mod imp {
                         .default_value(1.0)
                         .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
                         .build(),
+                    glib::ParamSpecBoolean::builder("links-on-top")
+                        .default_value(true)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("group-by-application")
+                        .default_value(false)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("show-link-labels")
+                        .default_value(false)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("show-link-endpoints")
+                        .default_value(false)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("show-grid")
+                        .default_value(true)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecFloat::builder("grid-size")
+                        .minimum(5.0)
+                        .maximum(200.0)
+                        .default_value(20.0)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecFloat::builder("link-tension")
+                        .minimum(0.0)
+                        .maximum(3.0)
+                        .default_value(1.0)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("dim-inactive-nodes")
+                        .default_value(true)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecDouble::builder("inactive-node-opacity")
+                        .minimum(0.0)
+                        .maximum(1.0)
+                        .default_value(0.5)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("show-sources")
+                        .default_value(true)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("show-sinks")
+                        .default_value(true)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("show-other-nodes")
+                        .default_value(true)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("wrap-scrolling")
+                        .default_value(false)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecEnum::builder_with_default(
+                        "orientation",
+                        gtk::Orientation::Horizontal,
+                    )
+                    .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                    .build(),
+                    glib::ParamSpecDouble::builder("zoom-gesture-sensitivity")
+                        .minimum(0.1)
+                        .maximum(5.0)
+                        .default_value(1.0)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecEnum::builder::<NodeHighlight>("node-highlight")
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecEnum::builder::<LinkFilter>("link-filter")
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecEnum::builder::<InactiveLinkStyle>("inactive-link-style")
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecEnum::builder::<LinkStyle>("link-style")
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("animate-zoom")
+                        .default_value(true)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("invert-zoom-scroll")
+                        .default_value(false)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("zoom-locked")
+                        .default_value(false)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    // A small margin keeps nodes that are partially scrolled off from popping in and out while panning.
+                    glib::ParamSpecInt::builder("culling-margin")
+                        .minimum(0)
+                        .default_value(64)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecDouble::builder("lod-threshold")
+                        .minimum(0.0)
+                        .maximum(4.0)
+                        .default_value(0.5)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("show-minimap")
+                        .default_value(false)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("show-diagnostics")
+                        .default_value(false)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("show-port-indices")
+                        .default_value(false)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
+                    // Off by default, as computing the density adds rendering cost on every frame.
+                    glib::ParamSpecBoolean::builder("show-link-density")
+                        .default_value(false)
+                        .flags(glib::ParamFlags::CONSTRUCT | glib::ParamFlags::READWRITE)
+                        .build(),
                 ]
             });
 
@@ -125,6 +643,34 @@ mod imp {
                 "vadjustment" => self.vadjustment.borrow().to_value(),
                 "hscroll-policy" | "vscroll-policy" => gtk::ScrollablePolicy::Natural.to_value(),
                 "zoom-factor" => self.zoom_factor.get().to_value(),
+                "show-link-endpoints" => self.show_link_endpoints.get().to_value(),
+                "show-link-labels" => self.show_link_labels.get().to_value(),
+                "group-by-application" => self.group_by_application.get().to_value(),
+                "links-on-top" => self.links_on_top.get().to_value(),
+                "show-grid" => self.show_grid.get().to_value(),
+                "grid-size" => self.grid_size.get().to_value(),
+                "link-tension" => self.link_tension.get().to_value(),
+                "dim-inactive-nodes" => self.dim_inactive_nodes.get().to_value(),
+                "inactive-node-opacity" => self.inactive_node_opacity.get().to_value(),
+                "show-sources" => self.show_sources.get().to_value(),
+                "show-sinks" => self.show_sinks.get().to_value(),
+                "show-other-nodes" => self.show_other_nodes.get().to_value(),
+                "wrap-scrolling" => self.wrap_scrolling.get().to_value(),
+                "show-link-density" => self.show_link_density.get().to_value(),
+                "show-port-indices" => self.show_port_indices.get().to_value(),
+                "show-minimap" => self.show_minimap.get().to_value(),
+                "lod-threshold" => self.lod_threshold.get().to_value(),
+                "show-diagnostics" => self.show_diagnostics.get().to_value(),
+                "culling-margin" => self.culling_margin.get().to_value(),
+                "zoom-locked" => self.zoom_locked.get().to_value(),
+                "invert-zoom-scroll" => self.invert_zoom_scroll.get().to_value(),
+                "animate-zoom" => self.animate_zoom.get().to_value(),
+                "node-highlight" => self.node_highlight.get().to_value(),
+                "link-filter" => self.link_filter.get().to_value(),
+                "inactive-link-style" => self.inactive_link_style.get().to_value(),
+                "link-style" => self.link_style.get().to_value(),
+                "zoom-gesture-sensitivity" => self.zoom_gesture_sensitivity.get().to_value(),
+                "orientation" => self.orientation().to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -142,6 +688,156 @@ mod imp {
                 "hscroll-policy" | "vscroll-policy" => {}
                 "zoom-factor" => {
                     self.zoom_factor.set(value.get().unwrap());
+                    self.update_level_of_detail();
+                    obj.queue_allocate();
+                }
+                "lod-threshold" => {
+                    self.lod_threshold.set(value.get().unwrap());
+                    self.update_level_of_detail();
+                    obj.queue_allocate();
+                }
+                "show-link-endpoints" => {
+                    self.show_link_endpoints.set(value.get().unwrap());
+                    obj.queue_draw();
+                }
+                "show-link-labels" => {
+                    self.show_link_labels.set(value.get().unwrap());
+                    obj.queue_draw();
+                }
+                "links-on-top" => {
+                    self.links_on_top.set(value.get().unwrap());
+                    obj.queue_draw();
+                }
+                "group-by-application" => {
+                    self.group_by_application.set(value.get().unwrap());
+                    // Collapsed groups hide their nodes only while groups are shown.
+                    self.update_node_visibility();
+                }
+                "show-grid" => {
+                    self.show_grid.set(value.get().unwrap());
+                    obj.queue_draw();
+                }
+                "grid-size" => {
+                    self.grid_size.set(value.get().unwrap());
+                    obj.queue_draw();
+                }
+                "link-tension" => {
+                    self.link_tension.set(value.get().unwrap());
+                    obj.queue_draw();
+                }
+                "dim-inactive-nodes" => {
+                    self.dim_inactive_nodes.set(value.get().unwrap());
+                    for (node, _) in self.nodes.borrow().values() {
+                        self.update_node_dimming(node);
+                    }
+                }
+                "inactive-node-opacity" => {
+                    let opacity: f64 = value.get().unwrap();
+                    self.inactive_node_opacity.set(opacity);
+                    self.dimming_style
+                        .load_from_data(&format!("node.inactive {{ opacity: {:.2}; }}", opacity));
+                }
+                "show-sources" | "show-sinks" | "show-other-nodes" => {
+                    match pspec.name() {
+                        "show-sources" => self.show_sources.set(value.get().unwrap()),
+                        "show-sinks" => self.show_sinks.set(value.get().unwrap()),
+                        _ => self.show_other_nodes.set(value.get().unwrap()),
+                    }
+                    self.update_node_visibility();
+                }
+                "wrap-scrolling" => self.wrap_scrolling.set(value.get().unwrap()),
+                "show-link-density" => {
+                    self.show_link_density.set(value.get().unwrap());
+                    obj.queue_draw();
+                }
+                "show-port-indices" => {
+                    self.show_port_indices.set(value.get().unwrap());
+                    for (node, _) in self.nodes.borrow().values() {
+                        node.set_show_port_indices(self.show_port_indices.get());
+                    }
+                }
+                "show-minimap" => {
+                    self.show_minimap.set(value.get().unwrap());
+                    obj.queue_draw();
+                }
+                "show-diagnostics" => {
+                    let show_diagnostics: bool = value.get().unwrap();
+                    self.show_diagnostics.set(show_diagnostics);
+
+                    let mut tick = self.diagnostics_tick.borrow_mut();
+                    if show_diagnostics && tick.is_none() {
+                        *tick = Some(obj.add_tick_callback(|graphview, frame_clock| {
+                            graphview.imp().diagnostics_fps.set(frame_clock.fps());
+                            graphview.queue_draw();
+                            glib::Continue(true)
+                        }));
+                    } else if !show_diagnostics {
+                        if let Some(tick) = tick.take() {
+                            tick.remove();
+                        }
+                    }
+                    obj.queue_draw();
+                }
+                "culling-margin" => {
+                    self.culling_margin.set(value.get().unwrap());
+                    obj.queue_draw();
+                }
+                "zoom-locked" => self.zoom_locked.set(value.get().unwrap()),
+                "invert-zoom-scroll" => self.invert_zoom_scroll.set(value.get().unwrap()),
+                "animate-zoom" => {
+                    self.animate_zoom.set(value.get().unwrap());
+                    // Jump to the end of a running animation, so it does not stop halfway.
+                    if let Some(animation) = self.zoom_animation.take() {
+                        animation.tick.remove();
+                        self.apply_zoom_factor(animation.target_zoom, animation.anchor);
+                    }
+                }
+                "node-highlight" => {
+                    self.node_highlight.set(value.get().unwrap());
+                    for (node, _) in self.nodes.borrow().values() {
+                        self.update_node_highlight(node);
+                    }
+                }
+                "link-filter" => {
+                    self.link_filter.set(value.get().unwrap());
+                    obj.queue_draw();
+                }
+                "inactive-link-style" => {
+                    self.inactive_link_style.set(value.get().unwrap());
+                    obj.queue_draw();
+                }
+                "link-style" => {
+                    self.link_style.set(value.get().unwrap());
+                    obj.queue_draw();
+                }
+                "zoom-gesture-sensitivity" => {
+                    self.zoom_gesture_sensitivity.set(value.get().unwrap())
+                }
+                "orientation" => {
+                    let orientation: gtk::Orientation = value.get().unwrap();
+                    if orientation == self.orientation() {
+                        return;
+                    }
+                    self.vertical_flow
+                        .set(orientation == gtk::Orientation::Vertical);
+
+                    // Flip the layout along the diagonal, so that columns of nodes become rows and vice versa.
+                    // Everything else placed on the canvas is flipped as well, so it stays where it belongs.
+                    let transpose = |point: &Point| Point::new(point.y(), point.x());
+                    for (node, point) in self.nodes.borrow_mut().values_mut() {
+                        node.set_orientation(orientation);
+                        *point = transpose(point);
+                    }
+                    for point in self.pending_positions.borrow_mut().values_mut() {
+                        *point = transpose(point);
+                    }
+                    for (_, point) in self.notes.borrow_mut().iter_mut() {
+                        *point = transpose(point);
+                    }
+                    for (_, rect) in self.frames.borrow_mut().iter_mut() {
+                        *rect = Rect::new(rect.y(), rect.x(), rect.height(), rect.width());
+                    }
+
                     obj.queue_allocate();
                 }
                 _ => unimplemented!(),
@@ -155,7 +851,26 @@ mod imp {
 
             let zoom_factor = self.zoom_factor.get();
 
+            // Frames have a fixed size on the canvas, so unlike nodes they grow and shrink with the zoom.
+            for (frame, rect) in self.frames.borrow().iter() {
+                let transform = self
+                    .canvas_space_to_screen_space_transform()
+                    .translate(&rect.origin());
+
+                frame.allocate(
+                    rect.width() as i32,
+                    rect.height() as i32,
+                    baseline,
+                    Some(transform),
+                );
+            }
+
             for (node, point) in self.nodes.borrow().values() {
+                // Hidden nodes are not allocated, so that they can not be picked.
+                if !self.is_node_shown(node) {
+                    continue;
+                }
+
                 let (_, natural_size) = node.preferred_size();
 
                 let transform = self
@@ -170,35 +885,157 @@ mod imp {
                 );
             }
 
+            // Notes are sized to their text like nodes are.
+            for (note, point) in self.notes.borrow().iter() {
+                let (_, natural_size) = note.preferred_size();
+
+                let transform = self
+                    .canvas_space_to_screen_space_transform()
+                    .translate(point);
+
+                note.allocate(
+                    (natural_size.width() as f64 / zoom_factor).ceil() as i32,
+                    (natural_size.height() as f64 / zoom_factor).ceil() as i32,
+                    baseline,
+                    Some(transform),
+                );
+            }
+
             if let Some(ref hadjustment) = *self.hadjustment.borrow() {
                 self.set_adjustment_values(widget, hadjustment, gtk::Orientation::Horizontal);
             }
             if let Some(ref vadjustment) = *self.vadjustment.borrow() {
                 self.set_adjustment_values(widget, vadjustment, gtk::Orientation::Vertical);
             }
+
+            if let Some(canvas_menu) = self.canvas_menu.get() {
+                canvas_menu.present();
+            }
+            if let Some(link_menu) = self.link_menu.get() {
+                link_menu.present();
+            }
         }
 
         fn snapshot(&self, snapshot: &gtk::Snapshot) {
             let widget = &*self.obj();
-            let alloc = widget.allocation();
+            let visible_area = self.culling_area();
+            let snapshot_start = Instant::now();
 
             self.snapshot_background(widget, snapshot);
 
-            // Draw all visible children
+            // Frames are drawn behind the nodes they group.
+            self.frames
+                .borrow()
+                .iter()
+                .filter(|(frame, _)| visible_area.intersect(&frame.allocation()).is_some())
+                .for_each(|(frame, _)| widget.snapshot_child(frame, snapshot));
+
+            if self.group_by_application.get() {
+                self.update_collapsed_group_rects();
+                self.snapshot_groups(widget, snapshot);
+            }
+
+            let links_on_top = self.links_on_top.get();
+            if !links_on_top {
+                self.snapshot_links(widget, snapshot);
+            }
+
+            // Draw all visible children, each on top of its shadow.
+            let shadow_color = widget
+                .style_context()
+                .lookup_color("graphview-node-shadow")
+                .unwrap_or(RGBA::new(0.0, 0.0, 0.0, 0.25));
             self.nodes
                 .borrow()
                 .values()
-                // Cull nodes from rendering when they are outside the visible canvas area
-                .filter(|(node, _)| alloc.intersect(&node.allocation()).is_some())
-                .for_each(|(node, _)| widget.snapshot_child(node, snapshot));
+                // Cull nodes from rendering when they are hidden or outside the visible canvas area
+                .filter(|(node, _)| self.is_node_shown(node))
+                .filter(|(node, _)| visible_area.intersect(&node.allocation()).is_some())
+                .for_each(|(node, _)| {
+                    self.snapshot_node_shadow(widget, snapshot, node, &shadow_color);
+                    widget.snapshot_child(node, snapshot);
+                });
+
+            self.notes
+                .borrow()
+                .iter()
+                .filter(|(note, _)| visible_area.intersect(&note.allocation()).is_some())
+                .for_each(|(note, _)| widget.snapshot_child(note, snapshot));
 
-            self.snapshot_links(widget, snapshot);
+            if links_on_top {
+                self.snapshot_links(widget, snapshot);
+            }
+            self.snapshot_link_preview(widget, snapshot);
+            self.snapshot_rubberband(widget, snapshot);
+            self.snapshot_minimap(widget, snapshot);
+
+            if self.show_diagnostics.get() {
+                self.diagnostics_snapshot_time.set(snapshot_start.elapsed());
+                self.snapshot_diagnostics(widget, snapshot, &visible_area);
+            }
         }
     }
 
     impl ScrollableImpl for GraphView {}
 
     impl GraphView {
+        /// Reallocate the children on the next frame, no matter how often this is called until then.
+        ///
+        /// Motion events can arrive several times per frame while dragging, and reallocating all nodes for each
+        /// of them makes dragging stutter on large graphs.
+        pub(super) fn queue_allocate_once(&self) {
+            let obj = self.obj();
+            // Tick callbacks only run while the view is mapped.
+            if !obj.is_mapped() {
+                obj.queue_allocate();
+                return;
+            }
+
+            let mut tick = self.allocation_tick.borrow_mut();
+            if tick.is_none() {
+                *tick = Some(obj.add_tick_callback(|graphview, _| {
+                    // Returning `Continue(false)` removes the callback, so the id is only dropped.
+                    graphview.imp().allocation_tick.take();
+                    graphview.queue_allocate();
+                    glib::Continue(false)
+                }));
+            }
+        }
+
+        /// Reallocate the children immediately if a reallocation is waiting for the next frame.
+        pub(super) fn flush_allocation(&self) {
+            if let Some(tick) = self.allocation_tick.take() {
+                tick.remove();
+                self.obj().queue_allocate();
+            }
+        }
+
+        /// Draw a soft shadow below the node, so that it stands out from the background.
+        fn snapshot_node_shadow(
+            &self,
+            widget: &super::GraphView,
+            snapshot: &gtk::Snapshot,
+            node: &Node,
+            color: &RGBA,
+        ) {
+            // Size of the shadow during neutral zoom (factor 1.0), scaled with the zoom like the node itself.
+            const NORMAL_SHADOW_OFFSET: f32 = 2.0;
+            const NORMAL_SHADOW_BLUR: f32 = 8.0;
+            const NORMAL_CORNER_RADIUS: f32 = 4.0;
+
+            let Some(bounds) = node.compute_bounds(widget) else { return };
+            let zoom_factor = self.zoom_factor.get() as f32;
+
+            snapshot.append_outset_shadow(
+                &gsk::RoundedRect::from_rect(bounds, NORMAL_CORNER_RADIUS * zoom_factor),
+                color,
+                0.0,
+                NORMAL_SHADOW_OFFSET * zoom_factor,
+                0.0,
+                NORMAL_SHADOW_BLUR * zoom_factor,
+            );
+        }
+
         /// Returns a [`gsk::Transform`] matrix that can translate from canvas space to screen space.
         ///
         /// Canvas space is non-zoomed, and (0, 0) is fixed at the middle of the graph. \
@@ -224,438 +1061,3109 @@ mod imp {
                 .unwrap()
         }
 
-        fn setup_node_dragging(&self) {
-            let drag_controller = gtk::GestureDrag::new();
+        /// Add or remove the `inactive` style class of the node, which dims it.
+        ///
+        /// The amount of dimming and the transition back to full opacity are controlled by the stylesheet.
+        pub(super) fn update_node_dimming(&self, node: &Node) {
+            if self.dim_inactive_nodes.get() && !node.is_active() {
+                node.add_css_class("inactive");
+            } else {
+                node.remove_css_class("inactive");
+            }
+        }
 
-            drag_controller.connect_drag_begin(|drag_controller, x, y| {
-                let widget = drag_controller
-                    .widget()
-                    .dynamic_cast::<super::GraphView>()
-                    .expect("drag-begin event is not on the GraphView");
-                let mut dragged_node = widget.imp().dragged_node.borrow_mut();
+        /// Count the connected ports of each of the specified nodes from the links and pass them on to the nodes.
+        pub(super) fn update_connected_ports(&self, node_ids: &[u32]) {
+            let nodes = self.nodes.borrow();
+            let links = self.links.borrow();
 
-                // pick() should at least return the widget itself.
-                let target = widget
-                    .pick(x, y, gtk::PickFlags::DEFAULT)
-                    .expect("drag-begin pick() did not return a widget");
-                *dragged_node = if target.ancestor(Port::static_type()).is_some() {
-                    // The user targeted a port, so the dragging should be handled by the Port
-                    // component instead of here.
-                    None
-                } else if let Some(target) = target.ancestor(Node::static_type()) {
-                    // The user targeted a Node without targeting a specific Port.
-                    // Drag the Node around the screen.
-                    let node = target.dynamic_cast_ref::<Node>().unwrap();
+            for node_id in node_ids {
+                let Some((node, _)) = nodes.get(node_id) else { continue };
 
-                    let Some(canvas_node_pos) = widget.node_position(node) else { return };
-                    let canvas_cursor_pos = widget
-                        .imp()
-                        .screen_space_to_canvas_space_transform()
-                        .transform_point(&Point::new(x as f32, y as f32));
+                let inputs: HashSet<u32> = links
+                    .values()
+                    .filter(|(link, _)| link.node_to == *node_id)
+                    .map(|(link, _)| link.port_to)
+                    .collect();
+                let outputs: HashSet<u32> = links
+                    .values()
+                    .filter(|(link, _)| link.node_from == *node_id)
+                    .map(|(link, _)| link.port_from)
+                    .collect();
 
-                    Some(DragState {
-                        node: node.clone().downgrade(),
-                        offset: Point::new(
-                            canvas_cursor_pos.x() - canvas_node_pos.x(),
-                            canvas_cursor_pos.y() - canvas_node_pos.y(),
-                        ),
-                    })
-                } else {
-                    None
+                node.set_connected_ports(inputs.len(), outputs.len());
+            }
+        }
+
+        /// Draw all nodes simplified if the view is zoomed out below the level of detail threshold,
+        /// or with all their ports otherwise.
+        pub(super) fn update_level_of_detail(&self) {
+            let simplified = self.zoom_factor.get() < self.lod_threshold.get();
+            for (node, _) in self.nodes.borrow().values() {
+                node.set_simplified(simplified);
+            }
+        }
+
+        /// Whether the node is shown, depending on which types of nodes are shown
+        /// and whether it is part of a collapsed group.
+        pub(super) fn is_node_shown(&self, node: &Node) -> bool {
+            self.is_node_type_shown(node) && self.collapsed_group_of(node).is_none()
+        }
+
+        /// Whether nodes of the same type as the node are shown.
+        fn is_node_type_shown(&self, node: &Node) -> bool {
+            match node.node_type() {
+                Some(NodeType::Output) => self.show_sources.get(),
+                Some(NodeType::Input) => self.show_sinks.get(),
+                None => self.show_other_nodes.get(),
+            }
+        }
+
+        /// Show or hide each node, depending on whether [`Self::is_node_shown`].
+        pub(super) fn update_node_visibility(&self) {
+            let obj = self.obj();
+            for (node, _) in self.nodes.borrow().values() {
+                node.set_child_visible(self.is_node_shown(node));
+            }
+            obj.queue_allocate();
+            obj.queue_draw();
+        }
+
+        /// Get the name of the application whose collapsed group the node is part of, if it is.
+        fn collapsed_group_of(&self, node: &Node) -> Option<String> {
+            if !self.group_by_application.get() {
+                return None;
+            }
+
+            node.application_name()
+                .filter(|application| self.collapsed_groups.borrow().contains(application))
+        }
+
+        /// Get the ids of the nodes of each application, by application name.
+        ///
+        /// Nodes whose type is currently not shown are left out.
+        fn application_groups(&self) -> BTreeMap<String, Vec<u32>> {
+            let mut groups: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+            for (id, (node, _)) in self.nodes.borrow().iter() {
+                if !self.is_node_type_shown(node) {
+                    continue;
                 }
-            });
-            drag_controller.connect_drag_update(|drag_controller, x, y| {
-                let widget = drag_controller
-                    .widget()
-                    .dynamic_cast::<super::GraphView>()
-                    .expect("drag-update event is not on the GraphView");
-                let dragged_node = widget.imp().dragged_node.borrow();
-                let Some(DragState { node, offset }) = dragged_node.as_ref() else { return };
-                let Some(node) = node.upgrade() else { return };
+                if let Some(application) = node.application_name() {
+                    groups.entry(application).or_default().push(*id);
+                }
+            }
+            groups
+        }
 
-                let (start_x, start_y) = drag_controller
-                    .start_point()
-                    .expect("Drag has no start point");
+        /// Get the rectangle of the group of the nodes with the specified ids in canvas space,
+        /// including space for its title.
+        ///
+        /// Collapsed groups only consist of their title, placed where the top left node of the group is.
+        fn group_rect(&self, members: &[u32], collapsed: bool) -> Option<Rect> {
+            let nodes = self.nodes.borrow();
+            let members: Vec<&(Node, Point)> =
+                members.iter().filter_map(|id| nodes.get(id)).collect();
 
-                let onscreen_node_origin = Point::new((start_x + x) as f32, (start_y + y) as f32);
-                let transform = widget.imp().screen_space_to_canvas_space_transform();
-                let canvas_node_origin = transform.transform_point(&onscreen_node_origin);
+            let left = members
+                .iter()
+                .map(|(_, point)| point.x())
+                .reduce(f32::min)?;
+            let top = members
+                .iter()
+                .map(|(_, point)| point.y())
+                .reduce(f32::min)?;
+            if collapsed {
+                return Some(Rect::new(
+                    left - GROUP_PADDING,
+                    top - GROUP_PADDING - GROUP_HEADER_HEIGHT,
+                    COLLAPSED_GROUP_WIDTH,
+                    GROUP_HEADER_HEIGHT,
+                ));
+            }
 
-                widget.move_node(
-                    &node,
-                    &Point::new(
-                        canvas_node_origin.x() - offset.x(),
-                        canvas_node_origin.y() - offset.y(),
-                    ),
-                );
-            });
-            self.obj().add_controller(drag_controller);
+            let right = members
+                .iter()
+                .map(|(node, point)| point.x() + node.width() as f32)
+                .reduce(f32::max)?;
+            let bottom = members
+                .iter()
+                .map(|(node, point)| point.y() + node.height() as f32)
+                .reduce(f32::max)?;
+            Some(Rect::new(
+                left - GROUP_PADDING,
+                top - GROUP_PADDING - GROUP_HEADER_HEIGHT,
+                right - left + 2.0 * GROUP_PADDING,
+                bottom - top + 2.0 * GROUP_PADDING + GROUP_HEADER_HEIGHT,
+            ))
         }
 
-        fn setup_scroll_zooming(&self) {
-            // We're only interested in the vertical axis, but for devices like touchpads,
-            // not capturing a small accidental horizontal move may cause the scroll to be disrupted if a widget
-            // higher up captures it instead.
-            let scroll_controller =
-                gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::BOTH_AXES);
+        /// Remember the rectangle of each collapsed application group, which its links attach to.
+        fn update_collapsed_group_rects(&self) {
+            let collapsed_groups = self.collapsed_groups.borrow();
+            let rects = self
+                .application_groups()
+                .into_iter()
+                .filter(|(application, _)| collapsed_groups.contains(application))
+                .filter_map(|(application, members)| {
+                    Some((application, self.group_rect(&members, true)?))
+                })
+                .collect();
+            *self.collapsed_group_rects.borrow_mut() = rects;
+        }
 
-            scroll_controller.connect_scroll(|eventcontroller, _, delta_y| {
-                let event = eventcontroller.current_event().unwrap(); // We are inside the event handler, so it must have an event
+        /// Get the application groups that are drawn, with their rectangle in screen space and whether they are collapsed.
+        ///
+        /// Groups of a single node are only drawn when collapsed, so that the node can be shown again.
+        fn drawn_groups(&self) -> Vec<(String, Vec<u32>, Rect, bool)> {
+            let canvas_to_screen = self.canvas_space_to_screen_space_transform();
+            let collapsed_groups = self.collapsed_groups.borrow();
 
-                if event
-                    .modifier_state()
-                    .contains(gdk::ModifierType::CONTROL_MASK)
-                {
-                    let widget = eventcontroller
-                        .widget()
-                        .downcast::<super::GraphView>()
-                        .unwrap();
-                    widget.set_zoom_factor(widget.zoom_factor() + (0.1 * -delta_y), None);
+            self.application_groups()
+                .into_iter()
+                .filter_map(|(application, members)| {
+                    let collapsed = collapsed_groups.contains(&application);
+                    if members.len() < 2 && !collapsed {
+                        return None;
+                    }
+                    let rect = self.group_rect(&members, collapsed)?;
+                    Some((
+                        application,
+                        members,
+                        canvas_to_screen.transform_bounds(&rect),
+                        collapsed,
+                    ))
+                })
+                .collect()
+        }
 
-                    gtk::Inhibit(true)
+        /// Get the name of the application group whose title is at the specified point in screen space.
+        fn group_title_at(&self, x: f64, y: f64) -> Option<String> {
+            if !self.group_by_application.get() {
+                return None;
+            }
+
+            let header_height = GROUP_HEADER_HEIGHT * self.zoom_factor.get() as f32;
+            self.drawn_groups()
+                .into_iter()
+                .find(|(_, _, rect, _)| {
+                    let title = Rect::new(rect.x(), rect.y(), rect.width(), header_height);
+                    title.contains_point(&Point::new(x as f32, y as f32))
+                })
+                .map(|(application, ..)| application)
+        }
+
+        /// Collapse the group of the application, or expand it if it is collapsed.
+        fn toggle_group_collapsed(&self, application: &str) {
+            {
+                let mut collapsed_groups = self.collapsed_groups.borrow_mut();
+                if !collapsed_groups.remove(application) {
+                    collapsed_groups.insert(application.to_string());
+                }
+            }
+            self.update_node_visibility();
+        }
+
+        /// Draw a box with the application name behind the nodes of each application.
+        fn snapshot_groups(&self, widget: &super::GraphView, snapshot: &gtk::Snapshot) {
+            // Corner radius of group boxes during neutral zoom (factor 1.0).
+            const NORMAL_CORNER_RADIUS: f32 = 8.0;
+
+            let style_context = widget.style_context();
+            let color = style_context
+                .lookup_color("graphview-group")
+                .unwrap_or(RGBA::new(0.5, 0.5, 0.5, 0.15));
+            let border_color = RGBA::new(color.red(), color.green(), color.blue(), 0.6);
+            let zoom_factor = self.zoom_factor.get() as f32;
+
+            for (application, members, rect, collapsed) in self.drawn_groups() {
+                let outline = gsk::RoundedRect::from_rect(rect, NORMAL_CORNER_RADIUS * zoom_factor);
+                snapshot.push_rounded_clip(&outline);
+                snapshot.append_color(&color, &rect);
+                snapshot.pop();
+                snapshot.append_border(&outline, &[1.0; 4], &[border_color; 4]);
+
+                // The title tells whether the group can be expanded or collapsed by clicking it.
+                let title = if collapsed {
+                    format!("▸ {} ({})", application, members.len())
                 } else {
-                    gtk::Inhibit(false)
+                    format!("▾ {}", application)
+                };
+                let layout = widget.create_pango_layout(Some(&title));
+                layout.set_width((rect.width() / zoom_factor * gtk::pango::SCALE as f32) as i32);
+                layout.set_ellipsize(gtk::pango::EllipsizeMode::End);
+                let (_, text_height) = layout.pixel_size();
+
+                snapshot.save();
+                snapshot.translate(&Point::new(rect.x(), rect.y()));
+                snapshot.scale(zoom_factor, zoom_factor);
+                snapshot.translate(&Point::new(
+                    GROUP_PADDING,
+                    (GROUP_HEADER_HEIGHT - text_height as f32) / 2.0,
+                ));
+                snapshot.append_layout(&layout, &style_context.color());
+                snapshot.restore();
+            }
+        }
+
+        /// Add or remove the `highlighted` style class of the node, depending on which kind of nodes are highlighted.
+        pub(super) fn update_node_highlight(&self, node: &Node) {
+            let highlighted = match self.node_highlight.get() {
+                NodeHighlight::None => false,
+                NodeHighlight::Sinks => matches!(node.node_type(), Some(NodeType::Input)),
+                NodeHighlight::Sources => matches!(node.node_type(), Some(NodeType::Output)),
+                NodeHighlight::Devices => node.device_kind().is_some(),
+            };
+
+            if highlighted {
+                node.add_css_class("highlighted");
+            } else {
+                node.remove_css_class("highlighted");
+            }
+        }
+
+        /// Emphasize the node if its name contains the current search query, or dim it otherwise.
+        ///
+        /// Without an active search, the node is shown normally.
+        pub(super) fn update_node_search_match(&self, node: &Node) {
+            let query = self.search_query.borrow();
+
+            if query.is_empty() {
+                node.remove_css_class("search-match");
+                node.remove_css_class("search-dimmed");
+            } else if node.name().to_lowercase().contains(query.as_str()) {
+                node.add_css_class("search-match");
+                node.remove_css_class("search-dimmed");
+            } else {
+                node.remove_css_class("search-match");
+                node.add_css_class("search-dimmed");
+            }
+        }
+
+        /// Let the view be panned by dragging with the middle mouse button.
+        fn setup_panning(&self) {
+            let pan_controller = gtk::GestureDrag::new();
+            pan_controller.set_button(gdk::BUTTON_MIDDLE);
+
+            pan_controller.connect_drag_begin(|pan_controller, _, _| {
+                let widget = pan_controller
+                    .widget()
+                    .downcast::<super::GraphView>()
+                    .unwrap();
+                let imp = widget.imp();
+
+                let hadjustment = imp.hadjustment.borrow();
+                let vadjustment = imp.vadjustment.borrow();
+                let (Some(hadjustment), Some(vadjustment)) =
+                    (hadjustment.as_ref(), vadjustment.as_ref())
+                else {
+                    return;
+                };
+                imp.pan_start
+                    .set(Some((hadjustment.value(), vadjustment.value())));
+                widget.set_cursor_from_name(Some("grabbing"));
+            });
+            pan_controller.connect_drag_update(|pan_controller, x, y| {
+                let widget = pan_controller
+                    .widget()
+                    .downcast::<super::GraphView>()
+                    .unwrap();
+                let imp = widget.imp();
+                let Some((start_h, start_v)) = imp.pan_start.get() else { return };
+
+                // The adjustments are in zoomed screen space, so the canvas follows the pointer at any zoom.
+                if let Some(ref hadjustment) = *imp.hadjustment.borrow() {
+                    hadjustment.set_value(start_h - x);
+                }
+                if let Some(ref vadjustment) = *imp.vadjustment.borrow() {
+                    vadjustment.set_value(start_v - y);
                 }
             });
-            self.obj().add_controller(scroll_controller);
+            pan_controller.connect_drag_end(|pan_controller, _, _| {
+                let widget = pan_controller
+                    .widget()
+                    .downcast::<super::GraphView>()
+                    .unwrap();
+                widget.imp().pan_start.set(None);
+                widget.set_cursor(None);
+            });
+
+            self.obj().add_controller(pan_controller);
         }
 
-        fn setup_zoom_gesture(&self) {
-            let zoom_gesture = gtk::GestureZoom::new();
-            zoom_gesture.connect_begin(|gesture, _| {
-                let widget = gesture.widget().downcast::<super::GraphView>().unwrap();
+        /// Change the zoom factor, keeping the anchor point in screen space in place.
+        ///
+        /// The anchor defaults to the center of the view. This does not stop a running zoom animation.
+        pub(super) fn apply_zoom_factor(&self, zoom_factor: f64, anchor: Option<(f64, f64)>) {
+            let obj = self.obj();
+            let zoom_factor =
+                zoom_factor.clamp(super::GraphView::ZOOM_MIN, super::GraphView::ZOOM_MAX);
 
-                widget
-                    .imp()
-                    .zoom_gesture_initial_zoom
-                    .set(Some(widget.zoom_factor()));
-                widget
-                    .imp()
-                    .zoom_gesture_anchor
-                    .set(gesture.bounding_box_center());
+            let (anchor_x_screen, anchor_y_screen) = anchor.unwrap_or_else(|| {
+                (
+                    obj.allocation().width() as f64 / 2.0,
+                    obj.allocation().height() as f64 / 2.0,
+                )
             });
-            zoom_gesture.connect_scale_changed(move |gesture, delta| {
-                let widget = gesture.widget().downcast::<super::GraphView>().unwrap();
 
-                let initial_zoom = widget
-                    .imp()
-                    .zoom_gesture_initial_zoom
-                    .get()
-                    .expect("Initial zoom not set during zoom gesture");
+            let old_zoom = self.zoom_factor.get();
+            let hadjustment_ref = self.hadjustment.borrow();
+            let vadjustment_ref = self.vadjustment.borrow();
+            let hadjustment = hadjustment_ref.as_ref().unwrap();
+            let vadjustment = vadjustment_ref.as_ref().unwrap();
 
-                widget.set_zoom_factor(initial_zoom * delta, gesture.bounding_box_center());
-            });
-            self.obj().add_controller(zoom_gesture);
-        }
+            let x_total = (anchor_x_screen + hadjustment.value()) / old_zoom;
+            let y_total = (anchor_y_screen + vadjustment.value()) / old_zoom;
 
-        fn snapshot_background(&self, widget: &super::GraphView, snapshot: &gtk::Snapshot) {
-            // Grid size and line width during neutral zoom (factor 1.0).
-            const NORMAL_GRID_SIZE: f32 = 20.0;
-            const NORMAL_GRID_LINE_WIDTH: f32 = 1.0;
+            let new_hadjustment = x_total * zoom_factor - anchor_x_screen;
+            let new_vadjustment = y_total * zoom_factor - anchor_y_screen;
 
-            let zoom_factor = self.zoom_factor.get();
-            let grid_size = NORMAL_GRID_SIZE * zoom_factor as f32;
-            let grid_line_width = NORMAL_GRID_LINE_WIDTH * zoom_factor as f32;
+            hadjustment.set_value(new_hadjustment);
+            vadjustment.set_value(new_vadjustment);
 
-            let alloc = widget.allocation();
+            obj.set_property("zoom-factor", zoom_factor);
+        }
 
-            // We need to offset the lines between 0 and (excluding) `grid_size` so the grid moves with
-            // the rest of the view when scrolling.
-            // The offset is rounded so the grid is always aligned to a row of pixels.
-            let hadj = self
-                .hadjustment
-                .borrow()
-                .as_ref()
-                .map(|hadjustment| hadjustment.value())
-                .unwrap_or(0.0);
-            let hoffset = (grid_size - (hadj as f32 % grid_size)) % grid_size;
-            let vadj = self
-                .vadjustment
-                .borrow()
-                .as_ref()
-                .map(|vadjustment| vadjustment.value())
-                .unwrap_or(0.0);
-            let voffset = (grid_size - (vadj as f32 % grid_size)) % grid_size;
+        /// Get how far an animation that started at `start_time` has progressed at the frame time `now`,
+        /// from 0.0 to 1.0.
+        ///
+        /// All animations of the view share this timing. They ease out, so they react immediately
+        /// and slow down towards their end, and finish right away if animations are disabled in the GTK settings.
+        fn animation_progress(&self, start_time: i64, now: i64) -> f64 {
+            // How long it takes to reach the target, in microseconds.
+            const ANIMATION_DURATION: f64 = 200_000.0;
 
-            snapshot.push_repeat(
-                &Rect::new(0.0, 0.0, alloc.width() as f32, alloc.height() as f32),
-                Some(&Rect::new(0.0, voffset, alloc.width() as f32, grid_size)),
-            );
-            let grid_color = RGBA::new(0.137, 0.137, 0.137, 1.0);
-            snapshot.append_linear_gradient(
-                &Rect::new(0.0, voffset, alloc.width() as f32, grid_line_width),
-                &Point::new(0.0, 0.0),
-                &Point::new(alloc.width() as f32, 0.0),
-                &[
-                    ColorStop::new(0.0, grid_color),
-                    ColorStop::new(1.0, grid_color),
-                ],
-            );
-            snapshot.pop();
+            if !self.obj().settings().is_gtk_enable_animations() {
+                return 1.0;
+            }
 
-            snapshot.push_repeat(
-                &Rect::new(0.0, 0.0, alloc.width() as f32, alloc.height() as f32),
-                Some(&Rect::new(hoffset, 0.0, grid_size, alloc.height() as f32)),
-            );
-            snapshot.append_linear_gradient(
-                &Rect::new(hoffset, 0.0, grid_line_width, alloc.height() as f32),
-                &Point::new(0.0, 0.0),
-                &Point::new(0.0, alloc.height() as f32),
-                &[
-                    ColorStop::new(0.0, grid_color),
-                    ColorStop::new(1.0, grid_color),
-                ],
-            );
-            snapshot.pop();
+            let progress = ((now - start_time) as f64 / ANIMATION_DURATION).min(1.0);
+            1.0 - (1.0 - progress).powi(3)
         }
 
-        fn snapshot_links(&self, widget: &super::GraphView, snapshot: &gtk::Snapshot) {
-            let alloc = widget.allocation();
+        /// Advance the running zoom animation to the time of the current frame.
+        fn zoom_animation_tick(&self, frame_clock: &gdk::FrameClock) -> glib::Continue {
+            let now = frame_clock.frame_time();
+            let (zoom_factor, anchor, finished) = {
+                let mut animation = self.zoom_animation.borrow_mut();
+                let Some(animation) = animation.as_mut() else { return glib::Continue(false) };
 
-            let link_cr = snapshot.append_cairo(&graphene::Rect::new(
-                0.0,
-                0.0,
-                alloc.width() as f32,
-                alloc.height() as f32,
-            ));
+                let start_time = *animation.start_time.get_or_insert(now);
+                let progress = self.animation_progress(start_time, now);
 
-            link_cr.set_line_width(2.0 * self.zoom_factor.get());
+                (
+                    animation.start_zoom
+                        + (animation.target_zoom - animation.start_zoom) * progress,
+                    animation.anchor,
+                    progress >= 1.0,
+                )
+            };
 
-            let rgba = widget
-                .style_context()
-                .lookup_color("graphview-link")
-                .unwrap_or(gtk::gdk::RGBA::BLACK);
+            // Every step keeps the anchor in place, so it stays fixed throughout the whole animation.
+            self.apply_zoom_factor(zoom_factor, anchor);
 
-            link_cr.set_source_rgba(
-                rgba.red().into(),
-                rgba.green().into(),
-                rgba.blue().into(),
-                rgba.alpha().into(),
-            );
+            if finished {
+                // Returning `Continue(false)` removes the callback, so the id is only dropped.
+                self.zoom_animation.take();
+                glib::Continue(false)
+            } else {
+                glib::Continue(true)
+            }
+        }
 
-            for (link, active) in self.links.borrow().values() {
-                // TODO: Do not draw links when they are outside the view
-                if let Some((from_x, from_y, to_x, to_y)) = self.get_link_coordinates(link) {
-                    link_cr.move_to(from_x, from_y);
+        /// Advance the running scroll animation to the time of the current frame.
+        fn scroll_animation_tick(&self, frame_clock: &gdk::FrameClock) -> glib::Continue {
+            let now = frame_clock.frame_time();
+            let ((h, v), finished) = {
+                let mut animation = self.scroll_animation.borrow_mut();
+                let Some(animation) = animation.as_mut() else { return glib::Continue(false) };
 
-                    // Use dashed line for inactive links, full line otherwise.
-                    if *active {
-                        link_cr.set_dash(&[], 0.0);
-                    } else {
-                        link_cr.set_dash(&[10.0, 5.0], 0.0);
-                    }
+                let start_time = *animation.start_time.get_or_insert(now);
+                let progress = self.animation_progress(start_time, now);
 
-                    // If the output port is farther right than the input port and they have
-                    // a similar y coordinate, apply a y offset to the control points
-                    // so that the curve sticks out a bit.
-                    let y_control_offset = if from_x > to_x {
-                        f64::max(0.0, 25.0 - (from_y - to_y).abs())
-                    } else {
-                        0.0
-                    };
+                let (start_h, start_v) = animation.start;
+                let (target_h, target_v) = animation.target;
+                (
+                    (
+                        start_h + (target_h - start_h) * progress,
+                        start_v + (target_v - start_v) * progress,
+                    ),
+                    progress >= 1.0,
+                )
+            };
 
-                    // Place curve control offset by half the x distance between the two points.
-                    // This makes the curve scale well for varying distances between the two ports,
-                    // especially when the output port is farther right than the input port.
-                    let half_x_dist = f64::abs(from_x - to_x) / 2.0;
-                    link_cr.curve_to(
-                        from_x + half_x_dist,
-                        from_y - y_control_offset,
-                        to_x - half_x_dist,
-                        to_y - y_control_offset,
-                        to_x,
-                        to_y,
-                    );
+            if let Some(ref hadjustment) = *self.hadjustment.borrow() {
+                hadjustment.set_value(h);
+            }
+            if let Some(ref vadjustment) = *self.vadjustment.borrow() {
+                vadjustment.set_value(v);
+            }
 
-                    if let Err(e) = link_cr.stroke() {
-                        warn!("Failed to draw graphview links: {}", e);
-                    };
-                } else {
-                    warn!("Could not get allocation of ports of link: {:?}", link);
-                }
+            if finished {
+                // Returning `Continue(false)` removes the callback, so the id is only dropped.
+                self.scroll_animation.take();
+                glib::Continue(false)
+            } else {
+                glib::Continue(true)
             }
         }
 
-        /// Get coordinates for the drawn link to start at and to end at.
+        /// Let nodes be focused with Tab and moved with the arrow keys, so the graph can be arranged without a mouse.
         ///
-        /// # Returns
-        /// `Some((from_x, from_y, to_x, to_y))` if all objects the links refers to exist as widgets.
-        fn get_link_coordinates(&self, link: &crate::PipewireLink) -> Option<(f64, f64, f64, f64)> {
-            let widget = &*self.obj();
-            let nodes = self.nodes.borrow();
+        /// Tab and Shift+Tab cycle through the shown nodes, Ctrl+Tab leaves the graph as usual in GTK.
+        fn setup_keyboard_navigation(&self) {
+            let obj = self.obj();
+            // Let Tab enter the graph from the rest of the window.
+            obj.set_focusable(true);
 
-            let output_port = &nodes.get(&link.node_from)?.0.get_port(link.port_from)?;
+            let key_controller = gtk::EventControllerKey::new();
+            key_controller.connect_key_pressed(|key_controller, keyval, _, state| {
+                let widget = key_controller
+                    .widget()
+                    .downcast::<super::GraphView>()
+                    .unwrap();
+                let imp = widget.imp();
+
+                if state.intersects(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::ALT_MASK) {
+                    return gtk::Inhibit(false);
+                }
 
-            let output_port_padding =
-                (output_port.allocated_width() - output_port.width()) as f64 / 2.0;
+                let focused_node = widget
+                    .focus_child()
+                    .and_then(|child| child.downcast::<Node>().ok());
+                let step = imp.grid_size.get();
+                let (dx, dy) = match keyval {
+                    gdk::Key::Tab => return imp.cycle_node_focus(focused_node.as_ref(), false),
+                    gdk::Key::ISO_Left_Tab => {
+                        return imp.cycle_node_focus(focused_node.as_ref(), true)
+                    }
+                    gdk::Key::Left => (-step, 0.0),
+                    gdk::Key::Right => (step, 0.0),
+                    gdk::Key::Up => (0.0, -step),
+                    gdk::Key::Down => (0.0, step),
+                    _ => return gtk::Inhibit(false),
+                };
 
-            let (from_x, from_y) = output_port.translate_coordinates(
-                widget,
-                output_port.width() as f64 + output_port_padding,
-                (output_port.height() / 2) as f64,
-            )?;
+                let Some(node) = focused_node.filter(|node| !node.is_locked()) else {
+                    return gtk::Inhibit(false);
+                };
+                let Some(position) = widget.node_position(&node) else {
+                    return gtk::Inhibit(false);
+                };
+                widget.move_node(&node, &Point::new(position.x() + dx, position.y() + dy));
 
-            let input_port = &nodes.get(&link.node_to)?.0.get_port(link.port_to)?;
+                gtk::Inhibit(true)
+            });
+            obj.add_controller(key_controller);
+        }
 
-            let input_port_padding =
-                (input_port.allocated_width() - input_port.width()) as f64 / 2.0;
+        /// Move the keyboard focus to the next shown node, or the previous one if `backwards` is set.
+        ///
+        /// Nodes are cycled in the order of their ids, so the order stays the same while nodes are moved around.
+        fn cycle_node_focus(&self, focused_node: Option<&Node>, backwards: bool) -> gtk::Inhibit {
+            let mut nodes: Vec<Node> = self
+                .nodes
+                .borrow()
+                .values()
+                .map(|(node, _)| node)
+                .filter(|node| node.is_child_visible())
+                .cloned()
+                .collect();
+            if nodes.is_empty() {
+                return gtk::Inhibit(false);
+            }
+            nodes.sort_unstable_by_key(|node| node.pipewire_id());
+            if backwards {
+                nodes.reverse();
+            }
 
-            let (to_x, to_y) = input_port.translate_coordinates(
-                widget,
-                -input_port_padding,
-                (input_port.height() / 2) as f64,
-            )?;
+            let next = focused_node
+                .and_then(|focused| nodes.iter().position(|node| node == focused))
+                .map_or(0, |index| (index + 1) % nodes.len());
+            nodes[next].grab_focus();
 
-            Some((from_x, from_y, to_x, to_y))
+            gtk::Inhibit(true)
         }
 
-        fn set_adjustment(
-            &self,
-            obj: &super::GraphView,
-            adjustment: Option<&gtk::Adjustment>,
-            orientation: gtk::Orientation,
-        ) {
-            match orientation {
-                gtk::Orientation::Horizontal => {
-                    *self.hadjustment.borrow_mut() = adjustment.cloned()
+        /// Let the link preview follow the pointer while a link is dragged out of a port.
+        fn setup_link_preview(&self) {
+            let drop_motion = gtk::DropControllerMotion::new();
+            drop_motion.connect_motion(|drop_motion, x, y| {
+                let widget = drop_motion.widget().downcast::<super::GraphView>().unwrap();
+                let imp = widget.imp();
+
+                if let Some((port_id, _)) = imp.link_preview.get() {
+                    imp.link_preview.set(Some((port_id, (x, y))));
+                    widget.queue_draw();
                 }
-                gtk::Orientation::Vertical => *self.vadjustment.borrow_mut() = adjustment.cloned(),
-                _ => unimplemented!(),
-            }
+            });
+            self.obj().add_controller(drop_motion);
+        }
+
+        fn setup_node_dragging(&self) {
+            let drag_controller = gtk::GestureDrag::new();
+
+            drag_controller.connect_drag_begin(|drag_controller, x, y| {
+                let widget = drag_controller
+                    .widget()
+                    .dynamic_cast::<super::GraphView>()
+                    .expect("drag-begin event is not on the GraphView");
+                let mut dragged_node = widget.imp().dragged_node.borrow_mut();
+                widget.imp().dragged_frame.replace(None);
+                widget.imp().dragged_note.replace(None);
+                widget.imp().rerouted_link.replace(None);
+                widget.imp().rubberband.set(None);
+
+                // The minimap is drawn on top of everything, so it takes precedence over what is below it.
+                let on_minimap = widget.imp().minimap_contains(x, y);
+                widget.imp().minimap_dragging.set(on_minimap);
+                if on_minimap {
+                    widget.imp().center_on_minimap_point(x, y);
+                    *dragged_node = None;
+                    return;
+                }
+
+                // pick() should at least return the widget itself.
+                let target = widget
+                    .pick(x, y, gtk::PickFlags::DEFAULT)
+                    .expect("drag-begin pick() did not return a widget");
+
+                // Grabbing a link next to one of its ends moves that end to another port.
+                // On the port itself, dragging creates a new link instead.
+                if target.ancestor(Port::static_type()).is_none() {
+                    if let Some((link, moving_input)) = widget.imp().link_end_at(x, y) {
+                        widget.imp().rerouted_link.replace(Some(RerouteState {
+                            link,
+                            moving_input,
+                            pointer: (x, y),
+                        }));
+                        *dragged_node = None;
+                        return;
+                    }
+                }
+                *dragged_node = if target.ancestor(Port::static_type()).is_some() {
+                    // The user targeted a port, so the dragging should be handled by the Port
+                    // component instead of here.
+                    None
+                } else if target.ancestor(gtk::Button::static_type()).is_some() {
+                    // Buttons on nodes, like the collapse button, should be clicked instead of dragging the node.
+                    None
+                } else if target
+                    .ancestor(Node::static_type())
+                    .and_then(|node| node.downcast::<Node>().ok())
+                    .map_or(false, |node| node.is_locked())
+                {
+                    // Locked nodes stay in place, but can still be selected and connected.
+                    None
+                } else if let Some(target) = target.ancestor(Node::static_type()) {
+                    // The user targeted a Node without targeting a specific Port.
+                    // Drag the Node around the screen.
+                    let node = target.dynamic_cast_ref::<Node>().unwrap();
+
+                    let Some(canvas_node_pos) = widget.node_position(node) else { return };
+                    let canvas_cursor_pos = widget
+                        .imp()
+                        .screen_space_to_canvas_space_transform()
+                        .transform_point(&Point::new(x as f32, y as f32));
+
+                    // Dragging a selected node moves the whole selection, except for locked nodes.
+                    let node_id = node.pipewire_id();
+                    let selected_nodes = widget.imp().selected_nodes.borrow();
+                    let selected = if selected_nodes.contains(&node_id) {
+                        let nodes = widget.imp().nodes.borrow();
+                        selected_nodes
+                            .iter()
+                            .filter(|id| **id != node_id)
+                            .filter_map(|id| nodes.get(id))
+                            .filter(|(node, _)| !node.is_locked())
+                            .map(|(node, point)| (node.downgrade(), *point))
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+
+                    Some(DragState {
+                        node: node.clone().downgrade(),
+                        offset: Point::new(
+                            canvas_cursor_pos.x() - canvas_node_pos.x(),
+                            canvas_cursor_pos.y() - canvas_node_pos.y(),
+                        ),
+                        start: canvas_node_pos,
+                        selected,
+                    })
+                } else if let Some(note) = target.ancestor(Note::static_type()) {
+                    // Only the header moves the note, the rest of it is used for editing the text.
+                    let note = note.downcast_ref::<Note>().unwrap();
+                    if note.is_drag_handle(&target) {
+                        widget.imp().begin_note_drag(note, x, y);
+                    }
+
+                    None
+                } else if let Some(frame) = target.ancestor(Frame::static_type()) {
+                    // Clicking the title edits it, so it can't be used for dragging.
+                    if target.ancestor(gtk::EditableLabel::static_type()).is_none() {
+                        let frame = frame.downcast_ref::<Frame>().unwrap();
+                        widget
+                            .imp()
+                            .begin_frame_drag(frame, frame.is_resize_handle(&target));
+                    }
+
+                    None
+                } else {
+                    // Dragging on empty canvas selects the nodes in the dragged out rectangle,
+                    // adding to the selection while Ctrl is held.
+                    if !drag_controller
+                        .current_event_state()
+                        .contains(gdk::ModifierType::CONTROL_MASK)
+                    {
+                        widget.clear_selection();
+                    }
+                    widget
+                        .imp()
+                        .rubberband
+                        .set(Some(Rect::new(x as f32, y as f32, 0.0, 0.0)));
+
+                    None
+                }
+            });
+            drag_controller.connect_drag_update(|drag_controller, x, y| {
+                let widget = drag_controller
+                    .widget()
+                    .dynamic_cast::<super::GraphView>()
+                    .expect("drag-update event is not on the GraphView");
+
+                if widget.imp().minimap_dragging.get() {
+                    let (start_x, start_y) = drag_controller
+                        .start_point()
+                        .expect("Drag has no start point");
+                    widget
+                        .imp()
+                        .center_on_minimap_point(start_x + x, start_y + y);
+                    return;
+                }
+                if let Some(reroute) = widget.imp().rerouted_link.borrow_mut().as_mut() {
+                    let (start_x, start_y) = drag_controller
+                        .start_point()
+                        .expect("Drag has no start point");
+                    reroute.pointer = (start_x + x, start_y + y);
+                    widget.queue_draw();
+                    return;
+                }
+                if let Some(frame_drag) = widget.imp().dragged_frame.borrow().as_ref() {
+                    widget.imp().update_frame_drag(frame_drag, x, y);
+                    return;
+                }
+                if let Some(note_drag) = widget.imp().dragged_note.borrow().as_ref() {
+                    widget
+                        .imp()
+                        .update_note_drag(drag_controller, note_drag, x, y);
+                    return;
+                }
+
+                let (start_x, start_y) = drag_controller
+                    .start_point()
+                    .expect("Drag has no start point");
+
+                if widget.imp().rubberband.get().is_some() {
+                    // Normalize the rectangle, as it may be dragged out in any direction.
+                    let rubberband = Rect::new(
+                        start_x.min(start_x + x) as f32,
+                        start_y.min(start_y + y) as f32,
+                        x.abs() as f32,
+                        y.abs() as f32,
+                    );
+                    widget.imp().rubberband.set(Some(rubberband));
+                    widget.queue_draw();
+                    return;
+                }
+
+                let dragged_node = widget.imp().dragged_node.borrow();
+                let Some(DragState {
+                    node,
+                    offset,
+                    start,
+                    selected,
+                }) = dragged_node.as_ref()
+                else {
+                    return;
+                };
+                let Some(node) = node.upgrade() else { return };
+
+                let onscreen_node_origin = Point::new((start_x + x) as f32, (start_y + y) as f32);
+                let transform = widget.imp().screen_space_to_canvas_space_transform();
+                let canvas_node_origin = transform.transform_point(&onscreen_node_origin);
+
+                let new_position = Point::new(
+                    canvas_node_origin.x() - offset.x(),
+                    canvas_node_origin.y() - offset.y(),
+                );
+                widget.move_node(&node, &new_position);
+
+                let delta = (new_position.x() - start.x(), new_position.y() - start.y());
+                for (other, other_start) in selected {
+                    if let Some(other) = other.upgrade() {
+                        widget.move_node(
+                            &other,
+                            &Point::new(other_start.x() + delta.0, other_start.y() + delta.1),
+                        );
+                    }
+                }
+            });
+            drag_controller.connect_drag_end(|drag_controller, _, _| {
+                let widget = drag_controller
+                    .widget()
+                    .dynamic_cast::<super::GraphView>()
+                    .expect("drag-end event is not on the GraphView");
+
+                if let Some(rubberband) = widget.imp().rubberband.take() {
+                    widget.select_nodes_in(&rubberband);
+                    widget.queue_draw();
+                }
+
+                if let Some(reroute) = widget.imp().rerouted_link.take() {
+                    widget.imp().finish_reroute(reroute);
+                    widget.queue_draw();
+                }
+
+                // Place the dragged nodes at their final position right away.
+                widget.imp().flush_allocation();
+            });
+            self.obj().add_controller(drag_controller);
+        }
+
+        /// Start moving or resizing the frame.
+        ///
+        /// When moving, the nodes inside the frame are moved along with it.
+        fn begin_frame_drag(&self, frame: &Frame, resizing: bool) {
+            let Some(start_rect) = self
+                .frames
+                .borrow()
+                .iter()
+                .find(|(other, _)| other == frame)
+                .map(|(_, rect)| *rect)
+            else {
+                return;
+            };
+
+            let nodes = if resizing {
+                Vec::new()
+            } else {
+                self.nodes
+                    .borrow()
+                    .values()
+                    .filter(|(node, point)| start_rect.contains_point(point) && !node.is_locked())
+                    .map(|(node, point)| (node.downgrade(), *point))
+                    .collect()
+            };
+
+            self.dragged_frame.replace(Some(FrameDragState {
+                frame: frame.downgrade(),
+                start_rect,
+                resizing,
+                nodes,
+            }));
+        }
+
+        /// Move or resize the dragged frame by the drag offset `(x, y)` in screen space.
+        fn update_frame_drag(&self, frame_drag: &FrameDragState, x: f64, y: f64) {
+            // Smallest size a frame can be resized to, so the title and resize handle stay usable.
+            const MIN_FRAME_SIZE: (f32, f32) = (120.0, 80.0);
+
+            let Some(frame) = frame_drag.frame.upgrade() else { return };
+            let obj = self.obj();
+
+            // Drag offsets are zoomed, but frames are sized and positioned in canvas space.
+            let zoom_factor = self.zoom_factor.get();
+            let (dx, dy) = ((x / zoom_factor) as f32, (y / zoom_factor) as f32);
+            let start = &frame_drag.start_rect;
+
+            let rect = if frame_drag.resizing {
+                Rect::new(
+                    start.x(),
+                    start.y(),
+                    (start.width() + dx).max(MIN_FRAME_SIZE.0),
+                    (start.height() + dy).max(MIN_FRAME_SIZE.1),
+                )
+            } else {
+                for (node, point) in &frame_drag.nodes {
+                    if let Some(node) = node.upgrade() {
+                        obj.move_node(&node, &Point::new(point.x() + dx, point.y() + dy));
+                    }
+                }
+
+                Rect::new(
+                    start.x() + dx,
+                    start.y() + dy,
+                    start.width(),
+                    start.height(),
+                )
+            };
+
+            if let Some((_, frame_rect)) = self
+                .frames
+                .borrow_mut()
+                .iter_mut()
+                .find(|(other, _)| *other == frame)
+            {
+                *frame_rect = rect;
+            }
+
+            obj.queue_allocate();
+        }
+
+        /// Start moving the note, which was grabbed at `(x, y)` in screen space.
+        fn begin_note_drag(&self, note: &Note, x: f64, y: f64) {
+            let Some(note_pos) = self
+                .notes
+                .borrow()
+                .iter()
+                .find(|(other, _)| other == note)
+                .map(|(_, point)| *point)
+            else {
+                return;
+            };
+
+            let cursor_pos = self
+                .screen_space_to_canvas_space_transform()
+                .transform_point(&Point::new(x as f32, y as f32));
+
+            self.dragged_note.replace(Some(NoteDragState {
+                note: note.downgrade(),
+                offset: Point::new(cursor_pos.x() - note_pos.x(), cursor_pos.y() - note_pos.y()),
+            }));
+        }
+
+        /// Move the dragged note so that it stays under the pointer, which moved by `(x, y)` in screen space.
+        fn update_note_drag(
+            &self,
+            drag_controller: &gtk::GestureDrag,
+            note_drag: &NoteDragState,
+            x: f64,
+            y: f64,
+        ) {
+            let Some(note) = note_drag.note.upgrade() else { return };
+            let Some((start_x, start_y)) = drag_controller.start_point() else { return };
+
+            let cursor_pos = self
+                .screen_space_to_canvas_space_transform()
+                .transform_point(&Point::new((start_x + x) as f32, (start_y + y) as f32));
+
+            if let Some((_, point)) = self
+                .notes
+                .borrow_mut()
+                .iter_mut()
+                .find(|(other, _)| *other == note)
+            {
+                *point = Point::new(
+                    cursor_pos.x() - note_drag.offset.x(),
+                    cursor_pos.y() - note_drag.offset.y(),
+                );
+            }
+
+            self.obj().queue_allocate();
+        }
+
+        fn setup_scroll_zooming(&self) {
+            // We're only interested in the vertical axis, but for devices like touchpads,
+            // not capturing a small accidental horizontal move may cause the scroll to be disrupted if a widget
+            // higher up captures it instead.
+            let scroll_controller =
+                gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::BOTH_AXES);
+
+            scroll_controller.connect_scroll(|eventcontroller, delta_x, delta_y| {
+                let event = eventcontroller.current_event().unwrap(); // We are inside the event handler, so it must have an event
+                let widget = eventcontroller
+                    .widget()
+                    .downcast::<super::GraphView>()
+                    .unwrap();
+
+                if event
+                    .modifier_state()
+                    .contains(gdk::ModifierType::CONTROL_MASK)
+                {
+                    let delta_y = if widget.imp().invert_zoom_scroll.get() {
+                        -delta_y
+                    } else {
+                        delta_y
+                    };
+                    // Continue from where a running animation is heading to, so quick scrolling adds up.
+                    widget
+                        .animate_zoom_factor(widget.target_zoom_factor() + (0.1 * -delta_y), None);
+
+                    gtk::Inhibit(true)
+                } else if widget.imp().wrap_scrolling.get() {
+                    // Scroll the view ourselves instead of letting the scrolled window do it,
+                    // as it would stop at the edges of the canvas.
+                    let unit = eventcontroller.unit();
+                    if let Some(ref hadjustment) = *widget.imp().hadjustment.borrow() {
+                        Self::scroll_wrapping(hadjustment, delta_x, unit);
+                    }
+                    if let Some(ref vadjustment) = *widget.imp().vadjustment.borrow() {
+                        Self::scroll_wrapping(vadjustment, delta_y, unit);
+                    }
+
+                    gtk::Inhibit(true)
+                } else {
+                    gtk::Inhibit(false)
+                }
+            });
+            self.obj().add_controller(scroll_controller);
+        }
+
+        /// Scroll the adjustment by `delta`, continuing from the opposite edge
+        /// when scrolling past one edge of the canvas.
+        ///
+        /// Mouse wheels scroll in steps of the adjustment, smooth scrolling e.g. on touchpads is already in pixels.
+        fn scroll_wrapping(adjustment: &gtk::Adjustment, delta: f64, unit: gdk::ScrollUnit) {
+            let lower = adjustment.lower();
+            let extent = adjustment.upper() - adjustment.page_size() - lower;
+            if extent <= 0.0 {
+                return;
+            }
+
+            let distance = if unit == gdk::ScrollUnit::Wheel {
+                delta * adjustment.step_increment()
+            } else {
+                delta
+            };
+            let value = adjustment.value() + distance;
+            adjustment.set_value(lower + (value - lower).rem_euclid(extent));
+        }
+
+        fn setup_zoom_gesture(&self) {
+            let zoom_gesture = gtk::GestureZoom::new();
+            zoom_gesture.connect_begin(|gesture, _| {
+                let widget = gesture.widget().downcast::<super::GraphView>().unwrap();
+
+                widget
+                    .imp()
+                    .zoom_gesture_initial_zoom
+                    .set(Some(widget.zoom_factor()));
+                widget
+                    .imp()
+                    .zoom_gesture_anchor
+                    .set(gesture.bounding_box_center());
+            });
+            zoom_gesture.connect_scale_changed(move |gesture, delta| {
+                let widget = gesture.widget().downcast::<super::GraphView>().unwrap();
+
+                let initial_zoom = widget
+                    .imp()
+                    .zoom_gesture_initial_zoom
+                    .get()
+                    .expect("Initial zoom not set during zoom gesture");
+
+                // Only scale how far the gesture deviates from the initial zoom,
+                // so that a sensitivity of 1.0 follows the fingers exactly.
+                let sensitivity = widget.imp().zoom_gesture_sensitivity.get();
+                let scale = 1.0 + (delta - 1.0) * sensitivity;
+
+                widget.animate_zoom_factor(initial_zoom * scale, gesture.bounding_box_center());
+            });
+            self.obj().add_controller(zoom_gesture);
+        }
+
+        fn setup_canvas_menu(&self) {
+            let menu = gio::Menu::new();
+            menu.append(Some("Add Frame"), Some("graph.add-frame"));
+            menu.append(Some("Add Note"), Some("graph.add-note"));
+            menu.append(Some("Paste Links"), Some("app.paste-links"));
+            menu.append(Some("Fit View"), Some("app.fit-view"));
+            menu.append(Some("Reset Zoom"), Some("win.set-zoom(1.0)"));
+            menu.append(Some("Arrange by Signal Flow"), Some("app.auto-layout"));
+            menu.append(Some("Show Grid"), Some("win.show-grid"));
+
+            let canvas_menu = gtk::PopoverMenu::from_model(Some(&menu));
+            canvas_menu.set_parent(&*self.obj());
+            canvas_menu.set_has_arrow(false);
+            self.canvas_menu
+                .set(canvas_menu)
+                .expect("Canvas menu already set");
+
+            let menu = gio::Menu::new();
+            menu.append(Some("Copy Link Info"), Some("graph.copy-link-info"));
+
+            let link_menu = gtk::PopoverMenu::from_model(Some(&menu));
+            link_menu.set_parent(&*self.obj());
+            link_menu.set_has_arrow(false);
+            self.link_menu
+                .set(link_menu)
+                .expect("Link menu already set");
+
+            let click_controller = gtk::GestureClick::new();
+            click_controller.set_button(gdk::BUTTON_SECONDARY);
+            click_controller.connect_pressed(|click_controller, _, x, y| {
+                let widget = click_controller
+                    .widget()
+                    .downcast::<super::GraphView>()
+                    .unwrap();
+
+                // Only show the menu when the canvas itself was clicked, not a node or port on it.
+                let target = widget.pick(x, y, gtk::PickFlags::DEFAULT);
+                if target.as_ref() != Some(widget.upcast_ref::<gtk::Widget>()) {
+                    return;
+                }
+
+                // Links are drawn onto the canvas, so they need to be picked separately.
+                let imp = widget.imp();
+                let link = imp.link_at(x, y);
+                imp.menu_link.set(link);
+                imp.menu_position.set((x, y));
+
+                let menu = if link.is_some() {
+                    imp.link_menu.get().expect("Link menu is not set")
+                } else {
+                    imp.canvas_menu.get().expect("Canvas menu is not set")
+                };
+                menu.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+                menu.popup();
+            });
+            self.obj().add_controller(click_controller);
+
+            // Clicking a link removes it. The click is not emitted if the pointer moved, so panning is unaffected.
+            // Clicking a port focuses it, so that only its links are shown, and clicking empty canvas clears the focus.
+            let link_click_controller = gtk::GestureClick::new();
+            link_click_controller.set_button(gdk::BUTTON_PRIMARY);
+            link_click_controller.connect_released(|click_controller, n_press, x, y| {
+                let widget = click_controller
+                    .widget()
+                    .downcast::<super::GraphView>()
+                    .unwrap();
+
+                if n_press != 1 || widget.imp().minimap_contains(x, y) {
+                    return;
+                }
+
+                let target = widget.pick(x, y, gtk::PickFlags::DEFAULT);
+                if let Some(port) = target
+                    .as_ref()
+                    .and_then(|target| target.ancestor(Port::static_type()))
+                {
+                    let port_id = port.downcast_ref::<Port>().unwrap().pipewire_id();
+                    // Clicking the focused port again clears the focus.
+                    let focused_port = widget.imp().focused_port.get();
+                    widget.set_focused_port((focused_port != Some(port_id)).then_some(port_id));
+                    return;
+                }
+                if let Some(node) = target
+                    .as_ref()
+                    .and_then(|target| target.ancestor(Node::static_type()))
+                {
+                    // Ctrl+click toggles the node in the selection, a plain click selects only this node.
+                    let node_id = node.downcast_ref::<Node>().unwrap().pipewire_id();
+                    node.grab_focus();
+                    if click_controller
+                        .current_event_state()
+                        .contains(gdk::ModifierType::CONTROL_MASK)
+                    {
+                        let selected = widget.imp().selected_nodes.borrow().contains(&node_id);
+                        widget.set_node_selected(node_id, !selected);
+                    } else {
+                        widget.clear_selection();
+                        widget.set_node_selected(node_id, true);
+                    }
+                    return;
+                }
+                if target.as_ref() != Some(widget.upcast_ref::<gtk::Widget>()) {
+                    return;
+                }
+                // Shortcuts of the graph only work while it has the focus, so take it away from e.g. the search entry.
+                widget.grab_focus();
+
+                // Clicking the title of an application group collapses or expands it.
+                if let Some(application) = widget.imp().group_title_at(x, y) {
+                    widget.imp().toggle_group_collapsed(&application);
+                    return;
+                }
+
+                let Some(link_id) = widget.link_at_position(x, y) else {
+                    widget.set_focused_port(None);
+                    return;
+                };
+                let link = widget
+                    .imp()
+                    .links
+                    .borrow()
+                    .get(&link_id)
+                    .map(|(link, _)| (link.port_from, link.port_to));
+                if let Some((port_from, port_to)) = link {
+                    widget.emit_by_name::<()>("link-remove-requested", &[&port_from, &port_to]);
+                }
+            });
+            self.obj().add_controller(link_click_controller);
+        }
+
+        fn setup_node_hovering(&self) {
+            let motion_controller = gtk::EventControllerMotion::new();
+            motion_controller.connect_motion(|motion_controller, x, y| {
+                let widget = motion_controller
+                    .widget()
+                    .downcast::<super::GraphView>()
+                    .unwrap();
+
+                let hovered_node = widget
+                    .pick(x, y, gtk::PickFlags::DEFAULT)
+                    .and_then(|target| target.ancestor(Node::static_type()))
+                    .and_then(|node| node.downcast::<Node>().ok())
+                    .map(|node| node.pipewire_id());
+
+                let imp = widget.imp();
+                if imp.hovered_node.replace(hovered_node) != hovered_node {
+                    widget.queue_draw();
+                }
+            });
+            motion_controller.connect_leave(|motion_controller| {
+                let widget = motion_controller.widget();
+                let widget = widget.downcast_ref::<super::GraphView>().unwrap();
+
+                if widget.imp().hovered_node.replace(None).is_some() {
+                    widget.queue_draw();
+                }
+            });
+            self.obj().add_controller(motion_controller);
+        }
+
+        fn snapshot_background(&self, widget: &super::GraphView, snapshot: &gtk::Snapshot) {
+            // Grid line width during neutral zoom (factor 1.0).
+            const NORMAL_GRID_LINE_WIDTH: f32 = 1.0;
+
+            let alloc = widget.allocation();
+
+            let background = widget.style_context().lookup_color("graphview-background");
+            if let Some(background) = background {
+                snapshot.append_color(
+                    &background,
+                    &Rect::new(0.0, 0.0, alloc.width() as f32, alloc.height() as f32),
+                );
+            }
+
+            if self.show_link_density.get() {
+                self.snapshot_link_density(widget, snapshot);
+            }
+
+            if !self.show_grid.get() {
+                return;
+            }
+
+            let zoom_factor = self.zoom_factor.get();
+            let grid_size = self.grid_size.get() * zoom_factor as f32;
+            let grid_line_width = NORMAL_GRID_LINE_WIDTH * zoom_factor as f32;
+
+            // We need to offset the lines between 0 and (excluding) `grid_size` so the grid moves with
+            // the rest of the view when scrolling.
+            // The offset is rounded so the grid is always aligned to a row of pixels.
+            let hadj = self
+                .hadjustment
+                .borrow()
+                .as_ref()
+                .map(|hadjustment| hadjustment.value())
+                .unwrap_or(0.0);
+            let hoffset = (grid_size - (hadj as f32 % grid_size)) % grid_size;
+            let vadj = self
+                .vadjustment
+                .borrow()
+                .as_ref()
+                .map(|vadjustment| vadjustment.value())
+                .unwrap_or(0.0);
+            let voffset = (grid_size - (vadj as f32 % grid_size)) % grid_size;
+
+            snapshot.push_repeat(
+                &Rect::new(0.0, 0.0, alloc.width() as f32, alloc.height() as f32),
+                Some(&Rect::new(0.0, voffset, alloc.width() as f32, grid_size)),
+            );
+            // Unless the theme sets a grid color, draw the grid slightly lighter than a dark background
+            // and slightly darker than a light one, so that it is visible on both.
+            let themed_grid_color = widget.style_context().lookup_color("graphview-grid");
+            let contrasting_grid_color = |background: RGBA| {
+                let luminance = 0.2126 * background.red()
+                    + 0.7152 * background.green()
+                    + 0.0722 * background.blue();
+                let shift = if luminance < 0.5 { 0.1 } else { -0.15 };
+                RGBA::new(
+                    (background.red() + shift).clamp(0.0, 1.0),
+                    (background.green() + shift).clamp(0.0, 1.0),
+                    (background.blue() + shift).clamp(0.0, 1.0),
+                    1.0,
+                )
+            };
+            let grid_color = themed_grid_color
+                .or_else(|| background.map(contrasting_grid_color))
+                .unwrap_or(RGBA::new(0.137, 0.137, 0.137, 1.0));
+            snapshot.append_linear_gradient(
+                &Rect::new(0.0, voffset, alloc.width() as f32, grid_line_width),
+                &Point::new(0.0, 0.0),
+                &Point::new(alloc.width() as f32, 0.0),
+                &[
+                    ColorStop::new(0.0, grid_color),
+                    ColorStop::new(1.0, grid_color),
+                ],
+            );
+            snapshot.pop();
+
+            snapshot.push_repeat(
+                &Rect::new(0.0, 0.0, alloc.width() as f32, alloc.height() as f32),
+                Some(&Rect::new(hoffset, 0.0, grid_size, alloc.height() as f32)),
+            );
+            snapshot.append_linear_gradient(
+                &Rect::new(hoffset, 0.0, grid_line_width, alloc.height() as f32),
+                &Point::new(0.0, 0.0),
+                &Point::new(0.0, alloc.height() as f32),
+                &[
+                    ColorStop::new(0.0, grid_color),
+                    ColorStop::new(1.0, grid_color),
+                ],
+            );
+            snapshot.pop();
+        }
+
+        /// Tint coarse cells of the canvas depending on how many nodes and link endpoints are in them,
+        /// so that busy areas of the graph stand out when zoomed out.
+        fn snapshot_link_density(&self, widget: &super::GraphView, snapshot: &gtk::Snapshot) {
+            // Size of a density cell in canvas space.
+            const CELL_SIZE: f32 = 200.0;
+            // Opacity of the tint for the densest cell.
+            const MAX_ALPHA: f32 = 0.3;
+
+            let cell_of = |point: &Point| {
+                (
+                    (point.x() / CELL_SIZE).floor() as i32,
+                    (point.y() / CELL_SIZE).floor() as i32,
+                )
+            };
+
+            let mut density: HashMap<(i32, i32), u32> = HashMap::new();
+            for (_, point) in self.nodes.borrow().values() {
+                *density.entry(cell_of(point)).or_default() += 1;
+            }
+
+            // Link coordinates are in screen space, so they need to be mapped back onto the canvas.
+            let screen_to_canvas = self.screen_space_to_canvas_space_transform();
+            for (link, _) in self.links.borrow().values() {
+                if let Some((from_x, from_y, to_x, to_y)) = self.get_link_coordinates(link) {
+                    for (x, y) in [(from_x, from_y), (to_x, to_y)] {
+                        let point =
+                            screen_to_canvas.transform_point(&Point::new(x as f32, y as f32));
+                        *density.entry(cell_of(&point)).or_default() += 1;
+                    }
+                }
+            }
+
+            let Some(max_density) = density.values().copied().max() else { return };
+
+            let color = widget
+                .style_context()
+                .lookup_color("graphview-density")
+                .unwrap_or(RGBA::new(0.94, 0.47, 0.16, 1.0));
+            let canvas_to_screen = self.canvas_space_to_screen_space_transform();
+
+            for ((x, y), count) in density {
+                let cell = Rect::new(
+                    x as f32 * CELL_SIZE,
+                    y as f32 * CELL_SIZE,
+                    CELL_SIZE,
+                    CELL_SIZE,
+                );
+                let alpha = color.alpha() * MAX_ALPHA * count as f32 / max_density as f32;
+
+                snapshot.append_color(
+                    &RGBA::new(color.red(), color.green(), color.blue(), alpha),
+                    &canvas_to_screen.transform_bounds(&cell),
+                );
+            }
+        }
+
+        /// Get the area of the minimap in screen space, if it is shown.
+        fn minimap_rect(&self) -> Option<Rect> {
+            if !self.show_minimap.get() {
+                return None;
+            }
+
+            let widget = self.obj();
+            Some(Rect::new(
+                widget.width() as f32 - MINIMAP_SIZE - MINIMAP_MARGIN,
+                widget.height() as f32 - MINIMAP_SIZE - MINIMAP_MARGIN,
+                MINIMAP_SIZE,
+                MINIMAP_SIZE,
+            ))
+        }
+
+        pub(super) fn minimap_contains(&self, x: f64, y: f64) -> bool {
+            self.minimap_rect().map_or(false, |rect| {
+                rect.contains_point(&Point::new(x as f32, y as f32))
+            })
+        }
+
+        /// Scroll the view so that the canvas point shown at the specified point of the minimap is centered.
+        pub(super) fn center_on_minimap_point(&self, x: f64, y: f64) {
+            let Some(rect) = self.minimap_rect() else { return };
+            let widget = self.obj();
+            let scale = f64::from(MINIMAP_SIZE) / CANVAS_SIZE;
+            let zoom_factor = self.zoom_factor.get();
+
+            let canvas_x = (x - f64::from(rect.x())) / scale - CANVAS_SIZE / 2.0;
+            let canvas_y = (y - f64::from(rect.y())) / scale - CANVAS_SIZE / 2.0;
+
+            if let Some(ref hadjustment) = *self.hadjustment.borrow() {
+                hadjustment.set_value(canvas_x * zoom_factor - f64::from(widget.width()) / 2.0);
+            }
+            if let Some(ref vadjustment) = *self.vadjustment.borrow() {
+                vadjustment.set_value(canvas_y * zoom_factor - f64::from(widget.height()) / 2.0);
+            }
+        }
+
+        /// Draw the frame rate, rendering time and size of the graph in the top left corner of the view.
+        fn snapshot_diagnostics(
+            &self,
+            widget: &super::GraphView,
+            snapshot: &gtk::Snapshot,
+            visible_area: &gtk::Allocation,
+        ) {
+            const PADDING: f32 = 6.0;
+
+            let nodes = self.nodes.borrow();
+            let visible_nodes = nodes
+                .values()
+                .filter(|(node, _)| visible_area.intersect(&node.allocation()).is_some())
+                .count();
+
+            let text = format!(
+                "{:.1} fps, snapshot {:.2} ms\n{} nodes ({} visible), {} links",
+                self.diagnostics_fps.get(),
+                self.diagnostics_snapshot_time.get().as_secs_f64() * 1000.0,
+                nodes.len(),
+                visible_nodes,
+                self.links.borrow().len()
+            );
+
+            let style_context = widget.style_context();
+            let background = style_context
+                .lookup_color("graphview-minimap")
+                .unwrap_or(RGBA::new(0.0, 0.0, 0.0, 0.3));
+
+            let layout = widget.create_pango_layout(Some(&text));
+            let (width, height) = layout.pixel_size();
+            snapshot.append_color(
+                &background,
+                &Rect::new(
+                    MINIMAP_MARGIN,
+                    MINIMAP_MARGIN,
+                    width as f32 + 2.0 * PADDING,
+                    height as f32 + 2.0 * PADDING,
+                ),
+            );
+
+            snapshot.save();
+            snapshot.translate(&Point::new(
+                MINIMAP_MARGIN + PADDING,
+                MINIMAP_MARGIN + PADDING,
+            ));
+            snapshot.append_layout(&layout, &style_context.color());
+            snapshot.restore();
+        }
+
+        /// Draw the rectangle that is being dragged out to select nodes.
+        fn snapshot_rubberband(&self, widget: &super::GraphView, snapshot: &gtk::Snapshot) {
+            let Some(rubberband) = self.rubberband.get() else { return };
+
+            let color = widget
+                .style_context()
+                .lookup_color("graphview-selection")
+                .unwrap_or(RGBA::BLACK);
+
+            snapshot.append_color(
+                &RGBA::new(
+                    color.red(),
+                    color.green(),
+                    color.blue(),
+                    color.alpha() * 0.2,
+                ),
+                &rubberband,
+            );
+            snapshot.append_border(
+                &gsk::RoundedRect::from_rect(rubberband, 0.0),
+                &[1.0; 4],
+                &[color; 4],
+            );
+        }
+
+        /// Draw an overview of the whole canvas, showing where the nodes are and which part of the canvas is visible.
+        fn snapshot_minimap(&self, widget: &super::GraphView, snapshot: &gtk::Snapshot) {
+            let Some(rect) = self.minimap_rect() else { return };
+
+            let style_context = widget.style_context();
+            let background = style_context
+                .lookup_color("graphview-minimap")
+                .unwrap_or(RGBA::new(0.0, 0.0, 0.0, 0.3));
+            let node_color = style_context
+                .lookup_color("graphview-link")
+                .unwrap_or(RGBA::BLACK);
+            let viewport_color = style_context
+                .lookup_color("graphview-selection")
+                .unwrap_or(RGBA::BLACK);
+
+            snapshot.append_color(&background, &rect);
+
+            // Maps canvas space onto the minimap.
+            let scale = MINIMAP_SIZE / CANVAS_SIZE as f32;
+            let to_minimap = |x: f32, y: f32| {
+                (
+                    rect.x() + (x + CANVAS_SIZE as f32 / 2.0) * scale,
+                    rect.y() + (y + CANVAS_SIZE as f32 / 2.0) * scale,
+                )
+            };
+
+            for (node, point) in self.nodes.borrow().values() {
+                let (x, y) = to_minimap(point.x(), point.y());
+                // Keep tiny nodes visible.
+                let width = (node.width() as f32 * scale).max(2.0);
+                let height = (node.height() as f32 * scale).max(2.0);
+                snapshot.append_color(&node_color, &Rect::new(x, y, width, height));
+            }
+
+            let zoom_factor = self.zoom_factor.get() as f32;
+            let (hadj, vadj) = match (&*self.hadjustment.borrow(), &*self.vadjustment.borrow()) {
+                (Some(hadjustment), Some(vadjustment)) => {
+                    (hadjustment.value() as f32, vadjustment.value() as f32)
+                }
+                _ => return,
+            };
+            let (x, y) = to_minimap(hadj / zoom_factor, vadj / zoom_factor);
+            let viewport = Rect::new(
+                x,
+                y,
+                widget.width() as f32 / zoom_factor * scale,
+                widget.height() as f32 / zoom_factor * scale,
+            );
+
+            let cr = snapshot.append_cairo(&rect);
+            cr.set_source_rgba(
+                viewport_color.red().into(),
+                viewport_color.green().into(),
+                viewport_color.blue().into(),
+                viewport_color.alpha().into(),
+            );
+            cr.set_line_width(1.5);
+            cr.rectangle(
+                viewport.x().into(),
+                viewport.y().into(),
+                viewport.width().into(),
+                viewport.height().into(),
+            );
+            if let Err(e) = cr.stroke() {
+                warn!("Failed to draw minimap viewport: {}", e);
+            }
+        }
+
+        /// Get the area in which children and links are drawn, which is the visible area extended by the culling margin.
+        fn culling_area(&self) -> gtk::Allocation {
+            let widget = self.obj();
+            let margin = self.culling_margin.get();
+
+            gtk::Allocation::new(
+                -margin,
+                -margin,
+                widget.width() + 2 * margin,
+                widget.height() + 2 * margin,
+            )
+        }
+
+        fn snapshot_links(&self, widget: &super::GraphView, snapshot: &gtk::Snapshot) {
+            let alloc = widget.allocation();
+            let visible_area = self.culling_area();
+
+            let link_cr = snapshot.append_cairo(&graphene::Rect::new(
+                0.0,
+                0.0,
+                alloc.width() as f32,
+                alloc.height() as f32,
+            ));
+
+            link_cr.set_line_width(2.0 * self.zoom_factor.get());
+            // Round caps and joins keep link ends looking clean regardless of zoom.
+            link_cr.set_line_cap(gtk::cairo::LineCap::Round);
+            link_cr.set_line_join(gtk::cairo::LineJoin::Round);
+
+            let style_context = widget.style_context();
+            let rgba = style_context
+                .lookup_color("graphview-link")
+                .unwrap_or(gtk::gdk::RGBA::BLACK);
+            // Links are colored like the ports they connect, falling back to the neutral color if the media type is unknown.
+            let media_color = |name: &str| style_context.lookup_color(name).unwrap_or(rgba);
+            let audio_rgba = media_color("graphview-link-audio");
+            let video_rgba = media_color("graphview-link-video");
+            let midi_rgba = media_color("graphview-link-midi");
+
+            for (link, active) in self.links.borrow().values() {
+                if !self.is_link_shown(link, *active) {
+                    continue;
+                }
+
+                if let Some((mut from_x, mut from_y, mut to_x, mut to_y)) =
+                    self.get_link_coordinates(link)
+                {
+                    // The end of a link that is being rerouted follows the pointer.
+                    if let Some(reroute) = self.rerouted_link.borrow().as_ref() {
+                        if reroute.link.port_from == link.port_from
+                            && reroute.link.port_to == link.port_to
+                        {
+                            if reroute.moving_input {
+                                (to_x, to_y) = reroute.pointer;
+                            } else {
+                                (from_x, from_y) = reroute.pointer;
+                            }
+                        }
+                    }
+
+                    let rgba = match link.media_type {
+                        Some(MediaType::Audio) => audio_rgba,
+                        Some(MediaType::Video) => video_rgba,
+                        Some(MediaType::Midi) => midi_rgba,
+                        None => rgba,
+                    };
+
+                    // While a node is hovered, dim all links that are not connected to it.
+                    let mut alpha = match self.hovered_node.get() {
+                        Some(node_id) if link.node_from != node_id && link.node_to != node_id => {
+                            rgba.alpha() * 0.25
+                        }
+                        _ => rgba.alpha(),
+                    };
+                    let inactive_style = self.inactive_link_style.get();
+                    if !*active && inactive_style == InactiveLinkStyle::Dimmed {
+                        alpha *= 0.35;
+                    }
+                    let color = RGBA::new(rgba.red(), rgba.green(), rgba.blue(), alpha);
+                    link_cr.set_source_rgba(
+                        color.red().into(),
+                        color.green().into(),
+                        color.blue().into(),
+                        color.alpha().into(),
+                    );
+
+                    // Use dashed line for inactive links, full line otherwise.
+                    if *active || inactive_style != InactiveLinkStyle::Dashed {
+                        link_cr.set_dash(&[], 0.0);
+                    } else {
+                        link_cr.set_dash(&Self::inactive_link_dash(self.zoom_factor.get()), 0.0);
+                    }
+
+                    self.append_link_path(&link_cr, (from_x, from_y), (to_x, to_y));
+
+                    // Do not draw links when they are outside the view.
+                    if let Ok((x1, y1, x2, y2)) = link_cr.stroke_extents() {
+                        let extents = gtk::Allocation::new(
+                            x1.floor() as i32,
+                            y1.floor() as i32,
+                            (x2 - x1).ceil() as i32 + 1,
+                            (y2 - y1).ceil() as i32 + 1,
+                        );
+                        if visible_area.intersect(&extents).is_none() {
+                            link_cr.new_path();
+                            continue;
+                        }
+                    }
+
+                    if let Err(e) = link_cr.stroke() {
+                        warn!("Failed to draw graphview links: {}", e);
+                    };
+                    self.append_link_arrowhead(&link_cr, (from_x, from_y), (to_x, to_y));
+
+                    if self.show_link_labels.get() {
+                        self.snapshot_link_label(
+                            widget,
+                            snapshot,
+                            link,
+                            &color,
+                            (from_x, from_y),
+                            (to_x, to_y),
+                        );
+                    }
+
+                    if self.show_link_endpoints.get() {
+                        self.snapshot_link_endpoints(
+                            &link_cr,
+                            &color,
+                            [(from_x, from_y), (to_x, to_y)],
+                        );
+                    }
+                } else {
+                    warn!("Could not get allocation of ports of link: {:?}", link);
+                }
+            }
+        }
+
+        /// Get the dash pattern used for inactive links at the specified zoom factor.
+        ///
+        /// The dashes scale with the zoom like everything else on the canvas,
+        /// so that inactive links look the same when zoomed out or in.
+        pub(super) fn inactive_link_dash(zoom_factor: f64) -> [f64; 2] {
+            // Dash and gap length during neutral zoom (factor 1.0).
+            const NORMAL_DASH_LENGTH: f64 = 10.0;
+            const NORMAL_GAP_LENGTH: f64 = 5.0;
+
+            [
+                NORMAL_DASH_LENGTH * zoom_factor,
+                NORMAL_GAP_LENGTH * zoom_factor,
+            ]
+        }
+
+        /// Add the path of a link from the output port at `from` to the input port at `to` to the cairo context.
+        fn append_link_path(
+            &self,
+            link_cr: &gtk::cairo::Context,
+            (from_x, from_y): (f64, f64),
+            (to_x, to_y): (f64, f64),
+        ) {
+            let Some(((control1_x, control1_y), (control2_x, control2_y))) =
+                self.link_control_points((from_x, from_y), (to_x, to_y))
+            else {
+                // If both ports are at nearly the same position, a curve between them would degenerate into
+                // a tiny squiggle or be invisible, so draw a small loop through both points instead.
+                let Some((center, loop_radius)) = link_loop(
+                    self.flow_coordinates(from_x, from_y),
+                    self.flow_coordinates(to_x, to_y),
+                    self.zoom_factor.get(),
+                ) else {
+                    return;
+                };
+                let (center_x, center_y) = self.flow_coordinates(center.0, center.1);
+
+                link_cr.new_sub_path();
+                link_cr.arc(
+                    center_x,
+                    center_y,
+                    loop_radius,
+                    0.0,
+                    2.0 * std::f64::consts::PI,
+                );
+                return;
+            };
+
+            link_cr.move_to(from_x, from_y);
+            if self.link_style.get() != LinkStyle::Orthogonal {
+                link_cr.curve_to(control1_x, control1_y, control2_x, control2_y, to_x, to_y);
+                return;
+            }
+
+            // Orthogonal links pass through their control points, which are the corners of the path.
+            // If the input is behind the output, the link makes a detour between both nodes.
+            let (corner1_x, corner1_y) = self.flow_coordinates(control1_x, control1_y);
+            let (corner2_x, corner2_y) = self.flow_coordinates(control2_x, control2_y);
+            let mut corners = vec![(corner1_x, corner1_y)];
+            if corner1_x > corner2_x {
+                let mid_y = (corner1_y + corner2_y) / 2.0;
+                corners.push((corner1_x, mid_y));
+                corners.push((corner2_x, mid_y));
+            }
+            corners.push((corner2_x, corner2_y));
+
+            for (x, y) in corners {
+                let (x, y) = self.flow_coordinates(x, y);
+                link_cr.line_to(x, y);
+            }
+            link_cr.line_to(to_x, to_y);
+        }
+
+        /// Get the two control points of the bezier curve of a link from `from` to `to` in screen space.
+        ///
+        /// Straight links have their control points on their ends, and orthogonal links on the corners
+        /// next to their ends, so that the direction into the input port can be taken from them for every style.
+        ///
+        /// Returns `None` if the ports are so close together that a loop is drawn instead of a curve.
+        fn link_control_points(
+            &self,
+            from: (f64, f64),
+            to: (f64, f64),
+        ) -> Option<((f64, f64), (f64, f64))> {
+            // The control points are computed in flow space and mapped back to screen space.
+            let (from_x, from_y) = self.flow_coordinates(from.0, from.1);
+            let (to_x, to_y) = self.flow_coordinates(to.0, to.1);
+
+            if link_loop((from_x, from_y), (to_x, to_y), self.zoom_factor.get()).is_some() {
+                return None;
+            }
+
+            match self.link_style.get() {
+                LinkStyle::Bezier => {}
+                LinkStyle::Straight => return Some((from, to)),
+                LinkStyle::Orthogonal => {
+                    // How far links leave their port before turning when the input is behind the output,
+                    // during neutral zoom (factor 1.0).
+                    const NORMAL_DETOUR_OFFSET: f64 = 20.0;
+
+                    let (corner1_x, corner2_x) = if from_x < to_x {
+                        let mid_x = (from_x + to_x) / 2.0;
+                        (mid_x, mid_x)
+                    } else {
+                        let offset = NORMAL_DETOUR_OFFSET * self.zoom_factor.get();
+                        (from_x + offset, to_x - offset)
+                    };
+                    return Some((
+                        self.flow_coordinates(corner1_x, from_y),
+                        self.flow_coordinates(corner2_x, to_y),
+                    ));
+                }
+            }
+
+            // If the output port is farther right than the input port and they have
+            // a similar y coordinate, apply a y offset to the control points
+            // so that the curve sticks out a bit.
+            let y_control_offset = if from_x > to_x {
+                f64::max(0.0, 25.0 - (from_y - to_y).abs())
+            } else {
+                0.0
+            };
+
+            // Place curve control offset by half the x distance between the two points.
+            // This makes the curve scale well for varying distances between the two ports,
+            // especially when the output port is farther right than the input port.
+            // The offset is scaled by the link tension, so that a tension of 0 results in a straight line.
+            let x_control_offset =
+                f64::abs(from_x - to_x) / 2.0 * f64::from(self.link_tension.get());
+
+            Some((
+                self.flow_coordinates(from_x + x_control_offset, from_y - y_control_offset),
+                self.flow_coordinates(to_x - x_control_offset, to_y - y_control_offset),
+            ))
+        }
+
+        /// Write the channel a link carries above the middle of its curve, in the specified color.
+        fn snapshot_link_label(
+            &self,
+            widget: &super::GraphView,
+            snapshot: &gtk::Snapshot,
+            link: &crate::PipewireLink,
+            color: &gdk::RGBA,
+            from: (f64, f64),
+            to: (f64, f64),
+        ) {
+            // Font size and distance of the label to the link during neutral zoom (factor 1.0).
+            const NORMAL_FONT_SIZE: f64 = 10.0;
+            const NORMAL_LABEL_OFFSET: f64 = 4.0;
+
+            let Some(label) = self.link_label(link) else { return };
+
+            // The middle of the curve is at t = 0.5 of the bezier, loops are labeled at their ports.
+            let (mid_x, mid_y) = match self.link_control_points(from, to) {
+                Some((control1, control2)) => (
+                    (from.0 + 3.0 * control1.0 + 3.0 * control2.0 + to.0) / 8.0,
+                    (from.1 + 3.0 * control1.1 + 3.0 * control2.1 + to.1) / 8.0,
+                ),
+                None => ((from.0 + to.0) / 2.0, (from.1 + to.1) / 2.0),
+            };
+
+            let layout = widget.create_pango_layout(Some(&label));
+            let mut font = layout
+                .context()
+                .font_description()
+                .unwrap_or_else(gtk::pango::FontDescription::new);
+            font.set_absolute_size(NORMAL_FONT_SIZE * f64::from(gtk::pango::SCALE));
+            layout.set_font_description(Some(&font));
+            let (width, height) = layout.pixel_size();
+
+            // The label is laid out at neutral zoom and scaled, so it keeps its proportions to the link.
+            let zoom_factor = self.zoom_factor.get();
+            snapshot.save();
+            snapshot.translate(&Point::new(mid_x as f32, mid_y as f32));
+            snapshot.scale(zoom_factor as f32, zoom_factor as f32);
+            snapshot.translate(&Point::new(
+                -width as f32 / 2.0,
+                -(height as f32) - NORMAL_LABEL_OFFSET as f32,
+            ));
+            snapshot.append_layout(&layout, color);
+            snapshot.restore();
+        }
+
+        /// Get the label of a link, which is the channel it carries if both ports name the same channel,
+        /// e.g. `FL` for a link from `output_FL` to `playback_FL`, or the names of both ports otherwise.
+        fn link_label(&self, link: &crate::PipewireLink) -> Option<String> {
+            let nodes = self.nodes.borrow();
+            let port_from = nodes
+                .get(&link.node_from)?
+                .0
+                .get_port(link.port_from)?
+                .name();
+            let port_to = nodes.get(&link.node_to)?.0.get_port(link.port_to)?.name();
+
+            let channel = |name: &str| name.rsplit('_').next().unwrap_or_default().to_string();
+            if port_from.contains('_') && channel(&port_from) == channel(&port_to) {
+                Some(channel(&port_from))
+            } else {
+                Some(format!("{} → {}", port_from, port_to))
+            }
+        }
+
+        /// Fill an arrowhead at the input port end of a link, pointing along the direction the curve arrives in.
+        fn append_link_arrowhead(
+            &self,
+            link_cr: &gtk::cairo::Context,
+            from: (f64, f64),
+            (to_x, to_y): (f64, f64),
+        ) {
+            // Length and width of the arrowhead during neutral zoom (factor 1.0).
+            const NORMAL_ARROW_LENGTH: f64 = 9.0;
+            const NORMAL_ARROW_WIDTH: f64 = 7.0;
+
+            // Loops have no direction to point in.
+            let Some((control1, control2)) = self.link_control_points(from, (to_x, to_y)) else {
+                return;
+            };
+
+            // The curve arrives from its second control point, unless that coincides with the end,
+            // as it does for straight links.
+            let (dx, dy) = [control2, control1, from]
+                .into_iter()
+                .map(|(x, y)| (to_x - x, to_y - y))
+                .find(|(dx, dy)| f64::hypot(*dx, *dy) > f64::EPSILON)
+                .unwrap_or((1.0, 0.0));
+            let distance = f64::hypot(dx, dy);
+            let (dx, dy) = (dx / distance, dy / distance);
+
+            let zoom_factor = self.zoom_factor.get();
+            let length = NORMAL_ARROW_LENGTH * zoom_factor;
+            let half_width = NORMAL_ARROW_WIDTH * zoom_factor / 2.0;
+            let (base_x, base_y) = (to_x - dx * length, to_y - dy * length);
+
+            link_cr.move_to(to_x, to_y);
+            link_cr.line_to(base_x - dy * half_width, base_y + dx * half_width);
+            link_cr.line_to(base_x + dy * half_width, base_y - dx * half_width);
+            link_cr.close_path();
+            if let Err(e) = link_cr.fill() {
+                warn!("Failed to draw link arrowhead: {}", e);
+            }
+        }
+
+        /// Get the default position of a new node, below the other nodes in its column.
+        ///
+        /// `placed` are the positions of the nodes that are already on the graph.
+        pub(super) fn default_node_position(
+            &self,
+            node_type: Option<NodeType>,
+            placed: impl Iterator<Item = Point>,
+        ) -> Point {
+            // Nodes are wider than they are high, so they need more space when stacked next to each other.
+            let spacing = if self.vertical_flow.get() {
+                250.0
+            } else {
+                120.0
+            };
+
+            // Place widgets in colums of 3, growing down.
+            // Positions are computed in flow space, so for a vertical flow these are rows growing right instead.
+            let x = if let Some(node_type) = node_type {
+                match node_type {
+                    NodeType::Output => 20.0,
+                    NodeType::Input => 820.0,
+                }
+            } else {
+                420.0
+            };
+
+            let y = placed
+                .map(|point| self.flow_coordinates(point.x(), point.y()))
+                .filter(|(x2, _)| {
+                    // Only look for other nodes that have a similar x coordinate
+                    (x - x2).abs() < 50.0
+                })
+                .max_by(|y1, y2| {
+                    // Get max in column
+                    y1.partial_cmp(y2).unwrap_or(Ordering::Equal)
+                })
+                .map_or(20_f32, |(_x, y)| y + spacing);
+
+            let (x, y) = self.flow_coordinates(x, y);
+            Point::new(x, y)
+        }
+
+        pub(super) fn orientation(&self) -> gtk::Orientation {
+            if self.vertical_flow.get() {
+                gtk::Orientation::Vertical
+            } else {
+                gtk::Orientation::Horizontal
+            }
+        }
+
+        /// Map coordinates between screen or canvas space and "flow space", in which signals always flow
+        /// from left to right.
+        ///
+        /// This swaps the axes if signals flow vertically, so that layout code written for a horizontal flow
+        /// also works for a vertical one. Applying it twice gives back the original coordinates.
+        pub(super) fn flow_coordinates<T>(&self, x: T, y: T) -> (T, T) {
+            if self.vertical_flow.get() {
+                (y, x)
+            } else {
+                (x, y)
+            }
+        }
+
+        /// Draw a small filled circle at both ends of a link, in the color the link is drawn in.
+        fn snapshot_link_endpoints(
+            &self,
+            link_cr: &gtk::cairo::Context,
+            color: &RGBA,
+            endpoints: [(f64, f64); 2],
+        ) {
+            // Dot radius during neutral zoom (factor 1.0).
+            const NORMAL_DOT_RADIUS: f64 = 3.0;
+
+            link_cr.set_source_rgba(
+                color.red().into(),
+                color.green().into(),
+                color.blue().into(),
+                color.alpha().into(),
+            );
+
+            for (x, y) in endpoints {
+                link_cr.new_sub_path();
+                link_cr.arc(
+                    x,
+                    y,
+                    NORMAL_DOT_RADIUS * self.zoom_factor.get(),
+                    0.0,
+                    2.0 * std::f64::consts::PI,
+                );
+            }
+
+            if let Err(e) = link_cr.fill() {
+                warn!("Failed to draw graphview link endpoints: {}", e);
+            }
+        }
+
+        /// Get the id of the link drawn at the specified point in screen space, if there is one.
+        pub(super) fn link_at(&self, x: f64, y: f64) -> Option<u32> {
+            // How far away from a link the point may be to still count as being on it, in pixels.
+            const PICK_TOLERANCE: f64 = 4.0;
+
+            // Cairo can only check whether a point is on a stroke through a context,
+            // so build the link paths on a context drawing to a dummy surface.
+            let surface =
+                gtk::cairo::ImageSurface::create(gtk::cairo::Format::ARgb32, 1, 1).ok()?;
+            let link_cr = gtk::cairo::Context::new(&surface).ok()?;
+            link_cr.set_line_width(2.0 * self.zoom_factor.get() + 2.0 * PICK_TOLERANCE);
+
+            self.links.borrow().iter().find_map(|(id, (link, active))| {
+                // Links that are not drawn can not be picked either.
+                if !self.is_link_shown(link, *active) {
+                    return None;
+                }
+                let (from_x, from_y, to_x, to_y) = self.get_link_coordinates(link)?;
+
+                link_cr.new_path();
+                self.append_link_path(&link_cr, (from_x, from_y), (to_x, to_y));
+
+                link_cr.in_stroke(x, y).unwrap_or(false).then_some(*id)
+            })
+        }
+
+        /// Find the shown link with an end close to the specified point in screen space.
+        ///
+        /// Returns the link and whether the close end is its input end.
+        fn link_end_at(&self, x: f64, y: f64) -> Option<(crate::PipewireLink, bool)> {
+            // How far away from the end of a link the point may be during neutral zoom (factor 1.0).
+            const NORMAL_GRAB_RADIUS: f64 = 8.0;
+
+            let radius = NORMAL_GRAB_RADIUS * self.zoom_factor.get();
+            self.links.borrow().values().find_map(|(link, active)| {
+                if !self.is_link_shown(link, *active) {
+                    return None;
+                }
+                let (from_x, from_y, to_x, to_y) = self.get_link_coordinates(link)?;
+
+                if f64::hypot(to_x - x, to_y - y) < radius {
+                    Some((link.clone(), true))
+                } else if f64::hypot(from_x - x, from_y - y) < radius {
+                    Some((link.clone(), false))
+                } else {
+                    None
+                }
+            })
+        }
+
+        /// Request moving the dragged end of the link to the port below the pointer.
+        ///
+        /// If there is no port of the right direction below the pointer, the link stays as it was.
+        fn finish_reroute(&self, reroute: RerouteState) {
+            let obj = self.obj();
+            let (x, y) = reroute.pointer;
+            let Some(port) = obj
+                .pick(x, y, gtk::PickFlags::DEFAULT)
+                .and_then(|target| target.ancestor(Port::static_type()))
+                .and_then(|port| port.downcast::<Port>().ok())
+            else {
+                return;
+            };
+
+            let link = reroute.link;
+            let (port_from, port_to) = match (reroute.moving_input, port.direction()) {
+                (true, Direction::Input) => (link.port_from, port.pipewire_id()),
+                (false, Direction::Output) => (port.pipewire_id(), link.port_to),
+                _ => return,
+            };
+            if (port_from, port_to) == (link.port_from, link.port_to) {
+                return;
+            }
+
+            obj.emit_by_name::<()>(
+                "link-reroute-requested",
+                &[&link.port_from, &link.port_to, &port_from, &port_to],
+            );
+        }
+
+        /// Whether the link is drawn, depending on the link filter, the focused port and whether its nodes are shown.
+        fn is_link_shown(&self, link: &crate::PipewireLink, active: bool) -> bool {
+            let focused = self.focused_port.get().map_or(true, |port_id| {
+                link.port_from == port_id || link.port_to == port_id
+            });
+
+            let hidden = !active && self.inactive_link_style.get() == InactiveLinkStyle::Hidden;
+            let nodes = self.nodes.borrow();
+            // Links of nodes in a collapsed group are drawn to the group instead.
+            let nodes_shown = [link.node_from, link.node_to].iter().all(|id| {
+                nodes
+                    .get(id)
+                    .map_or(true, |(node, _)| self.is_node_type_shown(node))
+            });
+            // Links between two nodes of the same collapsed group would start and end on the group.
+            let collapsed_group = |id: &u32| {
+                nodes
+                    .get(id)
+                    .and_then(|(node, _)| self.collapsed_group_of(node))
+            };
+            let within_group = collapsed_group(&link.node_from)
+                .map_or(false, |group| Some(group) == collapsed_group(&link.node_to));
+
+            focused
+                && nodes_shown
+                && !within_group
+                && !hidden
+                && self.link_filter.get().matches(active)
+        }
+
+        /// Describe the link in a human readable way, e.g. for pasting into bug reports.
+        fn describe_link(&self, link: &crate::PipewireLink, active: bool) -> String {
+            let nodes = self.nodes.borrow();
+
+            // Resolve ids to names, falling back to the ids if the node or port is unknown.
+            let endpoint = |node_id: u32, port_id: u32| {
+                let node = nodes.get(&node_id).map(|(node, _)| node);
+                let node_name = node.map_or_else(|| format!("Node {}", node_id), Node::name);
+                let port_name = node
+                    .and_then(|node| node.get_port(port_id))
+                    .map_or_else(|| format!("Port {}", port_id), |port| port.name());
+
+                format!("{}:{} (id {})", node_name, port_name, port_id)
+            };
+
+            let media_type = match link.media_type {
+                Some(MediaType::Audio) => "Audio",
+                Some(MediaType::Video) => "Video",
+                Some(MediaType::Midi) => "Midi",
+                None => "Unknown",
+            };
+
+            format!(
+                "{} -> {}\nMedia type: {}\nState: {}",
+                endpoint(link.node_from, link.port_from),
+                endpoint(link.node_to, link.port_to),
+                media_type,
+                if active { "active" } else { "inactive" }
+            )
+        }
+
+        /// Copy a description of the link the link menu was opened for to the clipboard.
+        fn copy_link_info(&self) {
+            let Some(link_id) = self.menu_link.get() else { return };
+
+            let description = match self.links.borrow().get(&link_id) {
+                Some((link, active)) => self.describe_link(link, *active),
+                None => {
+                    warn!(
+                        "Link (id={}) was removed before its info could be copied",
+                        link_id
+                    );
+                    return;
+                }
+            };
+
+            self.obj().clipboard().set_text(&description);
+        }
+
+        /// Get coordinates for the drawn link to start at and to end at.
+        ///
+        /// # Returns
+        /// `Some((from_x, from_y, to_x, to_y))` if all objects the links refers to exist as widgets.
+        fn get_link_coordinates(&self, link: &crate::PipewireLink) -> Option<(f64, f64, f64, f64)> {
+            let nodes = self.nodes.borrow();
+
+            let output_node = &nodes.get(&link.node_from)?.0;
+            let (from_x, from_y) =
+                self.port_anchor(output_node, &output_node.get_port(link.port_from)?)?;
+
+            let input_node = &nodes.get(&link.node_to)?.0;
+            let (to_x, to_y) = self.port_anchor(input_node, &input_node.get_port(link.port_to)?)?;
+
+            Some((from_x, from_y, to_x, to_y))
+        }
+
+        /// Get the point in screen space where links of the port attach to it.
+        ///
+        /// Links leave outputs on the right edge and enter inputs on the left edge,
+        /// or the bottom and top edges if signals flow vertically.
+        /// Simplified and collapsed nodes do not show their ports, so links attach to the edge of the node itself.
+        /// Nodes of a collapsed application group are hidden, so their links attach to the edge of the group.
+        fn port_anchor(&self, node: &Node, port: &Port) -> Option<(f64, f64)> {
+            let widget = &*self.obj();
+            let vertical = self.vertical_flow.get();
+
+            if let Some(application) = self.collapsed_group_of(node) {
+                let rect = *self.collapsed_group_rects.borrow().get(&application)?;
+                let rect = self
+                    .canvas_space_to_screen_space_transform()
+                    .transform_bounds(&rect);
+                let (x, y) = (f64::from(rect.x()), f64::from(rect.y()));
+                let (width, height) = (f64::from(rect.width()), f64::from(rect.height()));
+                return Some(match (port.direction(), vertical) {
+                    (Direction::Output, false) => (x + width, y + height / 2.0),
+                    (Direction::Output, true) => (x + width / 2.0, y + height),
+                    (Direction::Input, false) => (x, y + height / 2.0),
+                    (Direction::Input, true) => (x + width / 2.0, y),
+                });
+            }
+
+            if node.ports_hidden() {
+                let (width, height) = (node.width() as f64, node.height() as f64);
+                let (x, y) = match (port.direction(), vertical) {
+                    (Direction::Output, false) => (width, height / 2.0),
+                    (Direction::Output, true) => (width / 2.0, height),
+                    (Direction::Input, false) => (0.0, height / 2.0),
+                    (Direction::Input, true) => (width / 2.0, 0.0),
+                };
+                return node.translate_coordinates(widget, x, y);
+            }
+
+            let horizontal_padding = (port.allocated_width() - port.width()) as f64 / 2.0;
+            let vertical_padding = (port.allocated_height() - port.height()) as f64 / 2.0;
+            let (x, y) = match (port.direction(), vertical) {
+                (Direction::Output, false) => (
+                    port.width() as f64 + horizontal_padding,
+                    (port.height() / 2) as f64,
+                ),
+                (Direction::Output, true) => (
+                    (port.width() / 2) as f64,
+                    port.height() as f64 + vertical_padding,
+                ),
+                (Direction::Input, false) => (-horizontal_padding, (port.height() / 2) as f64),
+                (Direction::Input, true) => ((port.width() / 2) as f64, -vertical_padding),
+            };
+            port.translate_coordinates(widget, x, y)
+        }
+
+        /// Draw the link that is being dragged out of a port, from the port to the pointer.
+        fn snapshot_link_preview(&self, widget: &super::GraphView, snapshot: &gtk::Snapshot) {
+            let Some((port_id, pointer)) = self.link_preview.get() else { return };
+
+            let anchor = self.nodes.borrow().values().find_map(|(node, _)| {
+                let port = node.get_port(port_id)?;
+                Some((self.port_anchor(node, &port)?, *port.direction()))
+            });
+            let Some((anchor, direction)) = anchor else { return };
+
+            // Links always run from an output to an input, so dragging from an input ends the link at the port.
+            let (from, to) = match direction {
+                Direction::Output => (anchor, pointer),
+                Direction::Input => (pointer, anchor),
+            };
+
+            let alloc = widget.allocation();
+            let link_cr = snapshot.append_cairo(&graphene::Rect::new(
+                0.0,
+                0.0,
+                alloc.width() as f32,
+                alloc.height() as f32,
+            ));
+            let rgba = widget
+                .style_context()
+                .lookup_color("graphview-selection")
+                .unwrap_or(RGBA::BLACK);
+            link_cr.set_source_rgba(
+                rgba.red().into(),
+                rgba.green().into(),
+                rgba.blue().into(),
+                rgba.alpha().into(),
+            );
+            link_cr.set_line_width(2.0 * self.zoom_factor.get());
+            link_cr.set_line_cap(gtk::cairo::LineCap::Round);
+
+            self.append_link_path(&link_cr, from, to);
+            if let Err(e) = link_cr.stroke() {
+                warn!("Failed to draw link preview: {}", e);
+            }
+        }
+
+        fn set_adjustment(
+            &self,
+            obj: &super::GraphView,
+            adjustment: Option<&gtk::Adjustment>,
+            orientation: gtk::Orientation,
+        ) {
+            match orientation {
+                gtk::Orientation::Horizontal => {
+                    *self.hadjustment.borrow_mut() = adjustment.cloned()
+                }
+                gtk::Orientation::Vertical => *self.vadjustment.borrow_mut() = adjustment.cloned(),
+                _ => unimplemented!(),
+            }
+
+            if let Some(adjustment) = adjustment {
+                adjustment
+                    .connect_value_changed(clone!(@weak obj => move |_|  obj.queue_allocate() ));
+            }
+        }
+
+        pub(super) fn set_adjustment_values(
+            &self,
+            obj: &super::GraphView,
+            adjustment: &gtk::Adjustment,
+            orientation: gtk::Orientation,
+        ) {
+            let size = match orientation {
+                gtk::Orientation::Horizontal => obj.width(),
+                gtk::Orientation::Vertical => obj.height(),
+                _ => unimplemented!(),
+            };
+            let zoom_factor = self.zoom_factor.get();
+
+            adjustment.configure(
+                adjustment.value(),
+                -(CANVAS_SIZE / 2.0) * zoom_factor,
+                (CANVAS_SIZE / 2.0) * zoom_factor,
+                (f64::from(size) * 0.1) * zoom_factor,
+                (f64::from(size) * 0.9) * zoom_factor,
+                f64::from(size) * zoom_factor,
+            );
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct GraphView(ObjectSubclass<imp::GraphView>)
+        @extends gtk::Widget;
+}
+
+impl GraphView {
+    pub const ZOOM_MIN: f64 = 0.3;
+    pub const ZOOM_MAX: f64 = 4.0;
+    /// How much the zoom factor changes when zooming in or out by one step.
+    pub const ZOOM_STEP: f64 = 0.1;
+    /// Smaller zoom step used for precise framing, e.g. when holding Shift.
+    pub const ZOOM_STEP_FINE: f64 = 0.02;
+
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    pub fn inactive_link_style(&self) -> InactiveLinkStyle {
+        self.imp().inactive_link_style.get()
+    }
+
+    pub fn set_inactive_link_style(&self, style: InactiveLinkStyle) {
+        self.set_property("inactive-link-style", style);
+    }
+
+    pub fn link_style(&self) -> LinkStyle {
+        self.imp().link_style.get()
+    }
+
+    pub fn set_link_style(&self, style: LinkStyle) {
+        self.set_property("link-style", style);
+    }
+
+    pub fn zoom_factor(&self) -> f64 {
+        self.property("zoom-factor")
+    }
+
+    /// Set the scale factor.
+    ///
+    /// A factor of 1.0 is equivalent to 100% zoom, 0.5 to 50% zoom etc.
+    ///
+    /// An optional anchor (in canvas-space coordinates) can be specified, which will be used as the center of the zoom,
+    /// so that its position stays fixed.
+    /// If no anchor is specified, the middle of the screen is used instead.
+    ///
+    /// Note that the zoom level is [clamped](`f64::clamp`) to between 30% and 300%.
+    /// See [`Self::ZOOM_MIN`] and [`Self::ZOOM_MAX`].
+    ///
+    /// This does nothing while the `zoom-locked` property is set.
+    pub fn set_zoom_factor(&self, zoom_factor: f64, anchor: Option<(f64, f64)>) {
+        let imp = self.imp();
+        if imp.zoom_locked.get() {
+            return;
+        }
+
+        if let Some(animation) = imp.zoom_animation.take() {
+            animation.tick.remove();
+        }
+        imp.apply_zoom_factor(zoom_factor, anchor);
+    }
+
+    /// Smoothly change the zoom factor to the specified value over a short time,
+    /// keeping the anchor point in screen space in place, like [`Self::set_zoom_factor`].
+    ///
+    /// A running animation is continued towards the new zoom factor.
+    /// The zoom factor is changed immediately if the `animate-zoom` property is not set,
+    /// or on the next frame if animations are disabled in the GTK settings.
+    pub fn animate_zoom_factor(&self, zoom_factor: f64, anchor: Option<(f64, f64)>) {
+        let imp = self.imp();
+        if !imp.animate_zoom.get() || !self.is_mapped() {
+            self.set_zoom_factor(zoom_factor, anchor);
+            return;
+        }
+        if imp.zoom_locked.get() {
+            return;
+        }
+
+        let target_zoom = zoom_factor.clamp(Self::ZOOM_MIN, Self::ZOOM_MAX);
+        let start_zoom = self.zoom_factor();
+
+        let mut animation = imp.zoom_animation.borrow_mut();
+        let tick = match animation.take() {
+            Some(animation) => animation.tick,
+            None => self.add_tick_callback(|graphview, frame_clock| {
+                graphview.imp().zoom_animation_tick(frame_clock)
+            }),
+        };
+        *animation = Some(imp::ZoomAnimation {
+            start_zoom,
+            target_zoom,
+            anchor,
+            start_time: None,
+            tick,
+        });
+    }
+
+    /// Get the zoom factor a running zoom animation is heading to, or the current zoom factor otherwise.
+    pub fn target_zoom_factor(&self) -> f64 {
+        self.imp()
+            .zoom_animation
+            .borrow()
+            .as_ref()
+            .map_or_else(|| self.zoom_factor(), |animation| animation.target_zoom)
+    }
+
+    /// Add the node with the specified id to the selection or remove it from the selection.
+    pub fn set_node_selected(&self, node_id: u32, selected: bool) {
+        let imp = self.imp();
+        let Some((node, _)) = imp.nodes.borrow().get(&node_id).cloned() else { return };
+
+        if selected {
+            imp.selected_nodes.borrow_mut().insert(node_id);
+            node.add_css_class("selected");
+        } else {
+            imp.selected_nodes.borrow_mut().remove(&node_id);
+            node.remove_css_class("selected");
+        }
+    }
+
+    /// Get the ids of the selected nodes.
+    pub fn selected_nodes(&self) -> Vec<u32> {
+        self.imp().selected_nodes.borrow().iter().copied().collect()
+    }
+
+    pub fn clear_selection(&self) {
+        let imp = self.imp();
+        let nodes = imp.nodes.borrow();
+        for node_id in imp.selected_nodes.take() {
+            if let Some((node, _)) = nodes.get(&node_id) {
+                node.remove_css_class("selected");
+            }
+        }
+    }
+
+    /// Get the ids of all nodes whose name contains the query, ignoring case.
+    ///
+    /// The ids are ordered by the position of the nodes, from the top left to the bottom right.
+    pub fn nodes_matching(&self, query: &str) -> Vec<u32> {
+        let query = query.to_lowercase();
+        let nodes = self.imp().nodes.borrow();
+
+        let mut matching: Vec<(u32, Point)> = nodes
+            .iter()
+            .filter(|(_, (node, _))| node.name().to_lowercase().contains(&query))
+            .map(|(id, (_, point))| (*id, *point))
+            .collect();
+        matching.sort_by(|(_, a), (_, b)| {
+            a.x()
+                .total_cmp(&b.x())
+                .then_with(|| a.y().total_cmp(&b.y()))
+        });
+
+        matching.into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// Set the text of the node search.
+    ///
+    /// Nodes whose name contains the query, ignoring case, are emphasized and other nodes are dimmed,
+    /// including nodes added while the search is active. An empty query shows all nodes normally again.
+    pub fn set_search_query(&self, query: &str) {
+        let imp = self.imp();
+        *imp.search_query.borrow_mut() = query.to_lowercase();
+
+        for (node, _) in imp.nodes.borrow().values() {
+            imp.update_node_search_match(node);
+        }
+    }
+
+    /// Scroll the view so that the node with the specified id is centered.
+    ///
+    /// The view scrolls there smoothly while it is shown.
+    pub fn center_on_node(&self, node_id: u32) {
+        let imp = self.imp();
+        let Some((node, point)) = imp.nodes.borrow().get(&node_id).cloned() else { return };
+
+        let zoom_factor = self.zoom_factor();
+        let (_, natural_size) = node.preferred_size();
+        let center_x = f64::from(point.x()) + f64::from(natural_size.width()) / 2.0;
+        let center_y = f64::from(point.y()) + f64::from(natural_size.height()) / 2.0;
+        let target = (
+            center_x * zoom_factor - f64::from(self.width()) / 2.0,
+            center_y * zoom_factor - f64::from(self.height()) / 2.0,
+        );
+
+        let hadjustment = imp.hadjustment.borrow();
+        let vadjustment = imp.vadjustment.borrow();
+        let (Some(hadjustment), Some(vadjustment)) = (hadjustment.as_ref(), vadjustment.as_ref())
+        else {
+            return;
+        };
+
+        // Tick callbacks only run while the view is mapped.
+        if !self.is_mapped() {
+            hadjustment.set_value(target.0);
+            vadjustment.set_value(target.1);
+            return;
+        }
+
+        let mut animation = imp.scroll_animation.borrow_mut();
+        let tick = match animation.take() {
+            Some(animation) => animation.tick,
+            None => self.add_tick_callback(|graphview, frame_clock| {
+                graphview.imp().scroll_animation_tick(frame_clock)
+            }),
+        };
+        *animation = Some(imp::ScrollAnimation {
+            start: (hadjustment.value(), vadjustment.value()),
+            target,
+            start_time: None,
+            tick,
+        });
+    }
+
+    /// Select all nodes that intersect the specified rectangle in screen space.
+    fn select_nodes_in(&self, rect: &graphene::Rect) {
+        let node_ids: Vec<u32> = self
+            .imp()
+            .nodes
+            .borrow()
+            .iter()
+            .filter(|(_, (node, _))| {
+                node.compute_bounds(self)
+                    .map_or(false, |bounds| bounds.intersection(rect).is_some())
+            })
+            .map(|(id, _)| *id)
+            .collect();
+
+        for node_id in node_ids {
+            self.set_node_selected(node_id, true);
+        }
+    }
+
+    /// Get the id of the link drawn at the specified point in screen space, if there is one.
+    ///
+    /// The link is hit within a small band around the drawn curve, which grows with the links width when zooming in.
+    pub fn link_at_position(&self, x: f64, y: f64) -> Option<u32> {
+        self.imp().link_at(x, y)
+    }
+
+    /// Zoom and scroll the view so that all nodes are visible and centered.
+    ///
+    /// If there are no nodes, the zoom is reset and the view is centered on the canvas origin.
+    pub fn fit_to_view(&self) {
+        self.fit_to(|_| true);
+    }
+
+    /// Zoom and scroll the view so that the nodes with the specified ids are visible and centered.
+    ///
+    /// If no ids are specified, this fits all nodes like [`Self::fit_to_view()`].
+    pub fn fit_to_nodes(&self, ids: &[u32]) {
+        if ids.is_empty() {
+            self.fit_to_view();
+        } else {
+            self.fit_to(|id| ids.contains(&id));
+        }
+    }
+
+    /// Zoom and scroll the view so that the nodes whose ids pass the filter are visible and centered.
+    fn fit_to(&self, filter: impl Fn(u32) -> bool) {
+        // Space left around the nodes, in canvas space.
+        const PADDING: f64 = 50.0;
+
+        let imp = self.imp();
+        let width = f64::from(self.width());
+        let height = f64::from(self.height());
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+
+        let bounds = imp
+            .nodes
+            .borrow()
+            .iter()
+            .filter(|(id, _)| filter(**id))
+            .map(|(_, (node, point))| {
+                let (_, natural_size) = node.preferred_size();
+                let (x, y) = (f64::from(point.x()), f64::from(point.y()));
+                (
+                    x,
+                    y,
+                    x + f64::from(natural_size.width()),
+                    y + f64::from(natural_size.height()),
+                )
+            })
+            .reduce(|(x1, y1, x2, y2), (a1, b1, a2, b2)| {
+                (x1.min(a1), y1.min(b1), x2.max(a2), y2.max(b2))
+            });
+
+        let (zoom_factor, center_x, center_y) = match bounds {
+            Some((x1, y1, x2, y2)) => {
+                let zoom_factor = f64::min(
+                    width / (x2 - x1 + 2.0 * PADDING),
+                    height / (y2 - y1 + 2.0 * PADDING),
+                );
+                (zoom_factor, (x1 + x2) / 2.0, (y1 + y2) / 2.0)
+            }
+            None => (1.0, 0.0, 0.0),
+        };
+
+        // Only center the graph when the zoom is locked.
+        let zoom_factor = if imp.zoom_locked.get() {
+            self.zoom_factor()
+        } else {
+            zoom_factor.clamp(Self::ZOOM_MIN, Self::ZOOM_MAX)
+        };
+        self.set_property("zoom-factor", zoom_factor);
+
+        let hadjustment_ref = imp.hadjustment.borrow();
+        let vadjustment_ref = imp.vadjustment.borrow();
+        let hadjustment = hadjustment_ref.as_ref().unwrap();
+        let vadjustment = vadjustment_ref.as_ref().unwrap();
+
+        // Update the bounds of the adjustments to the new zoom factor first, so that the new values are not clamped.
+        imp.set_adjustment_values(self, hadjustment, gtk::Orientation::Horizontal);
+        imp.set_adjustment_values(self, vadjustment, gtk::Orientation::Vertical);
+
+        hadjustment.set_value(center_x * zoom_factor - width / 2.0);
+        vadjustment.set_value(center_y * zoom_factor - height / 2.0);
+    }
+
+    pub fn link_tension(&self) -> f32 {
+        self.property("link-tension")
+    }
+
+    /// Set how curvy links are drawn.
+    ///
+    /// At a tension of 0.0, links are drawn as nearly straight lines, higher values exaggerate the S-curve.
+    /// The default tension is 1.0.
+    pub fn set_link_tension(&self, tension: f32) {
+        self.set_property("link-tension", tension);
+    }
+
+    pub fn add_node(&self, id: u32, node: Node, node_type: Option<NodeType>) {
+        let imp = self.imp();
+        node.set_orientation(imp.orientation());
+        node.set_node_type(node_type);
+        node.set_parent(self);
+        imp.update_node_highlight(&node);
+        imp.update_node_search_match(&node);
+        node.set_simplified(self.zoom_factor() < imp.lod_threshold.get());
+        // Applied above the application stylesheet, which is loaded with application priority.
+        node.style_context().add_provider(
+            &imp.dimming_style,
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION + 2,
+        );
+        node.set_child_visible(imp.is_node_shown(&node));
+
+        // Favorites are kept by name, so a node stays a favorite when it disappears and reappears.
+        node.set_favorite(imp.favorites.borrow().contains(&node.name()));
+        node.connect_notify_local(
+            Some("favorite"),
+            clone!(@weak self as graphview => move |node, _| {
+                let mut favorites = graphview.imp().favorites.borrow_mut();
+                if node.is_favorite() {
+                    favorites.insert(node.name());
+                } else {
+                    favorites.remove(&node.name());
+                }
+            }),
+        );
+
+        // Links of a collapsed node attach to its edge instead of its ports, so they need to be redrawn.
+        node.connect_notify_local(
+            Some("collapsed"),
+            clone!(@weak self as graphview => move |_, _| graphview.queue_draw()),
+        );
+
+        // Use a position requested before the node appeared, if there is one.
+        let position = take_initial_position(
+            &mut imp.pending_positions.borrow_mut(),
+            &node.node_name(),
+            || {
+                let nodes = imp.nodes.borrow();
+                imp.default_node_position(node_type, nodes.values().map(|(_, point)| *point))
+            },
+        );
+
+        imp.nodes.borrow_mut().insert(id, (node, position));
+        self.emit_by_name::<()>("graph-changed", &[]);
+    }
+
+    /// Add a frame with a default size whose top left corner is at the specified point in screen space.
+    pub fn add_frame_at(&self, x: f64, y: f64) {
+        // Size of a new frame in canvas space.
+        const DEFAULT_FRAME_SIZE: (f32, f32) = (400.0, 300.0);
+
+        let imp = self.imp();
+        let origin = imp
+            .screen_space_to_canvas_space_transform()
+            .transform_point(&Point::new(x as f32, y as f32));
+
+        let frame = Frame::new("Group");
+        frame.connect_local(
+            "remove-requested",
+            false,
+            clone!(@weak self as graphview => @default-return None, move |args| {
+                let frame = args[0].get::<Frame>().unwrap();
+                graphview.remove_frame(&frame);
+
+                None
+            }),
+        );
+
+        // Frames are inserted as the first children, so that nodes stay on top of them when picking a widget.
+        frame.insert_after(self, gtk::Widget::NONE);
+
+        imp.frames.borrow_mut().push((
+            frame,
+            graphene::Rect::new(
+                origin.x(),
+                origin.y(),
+                DEFAULT_FRAME_SIZE.0,
+                DEFAULT_FRAME_SIZE.1,
+            ),
+        ));
+        self.queue_allocate();
+    }
+
+    /// Remove the frame from the graph, leaving the nodes inside it where they are.
+    pub fn remove_frame(&self, frame: &Frame) {
+        let mut frames = self.imp().frames.borrow_mut();
+        if let Some(index) = frames.iter().position(|(other, _)| other == frame) {
+            let (frame, _) = frames.remove(index);
+            frame.unparent();
+        }
+    }
+
+    /// Add an empty note whose top left corner is at the specified point in screen space.
+    pub fn add_note_at(&self, x: f64, y: f64) {
+        let imp = self.imp();
+        let position = imp
+            .screen_space_to_canvas_space_transform()
+            .transform_point(&Point::new(x as f32, y as f32));
+
+        let note = Note::new("");
+        note.connect_local(
+            "remove-requested",
+            false,
+            clone!(@weak self as graphview => @default-return None, move |args| {
+                let note = args[0].get::<Note>().unwrap();
+                graphview.remove_note(&note);
+
+                None
+            }),
+        );
+        note.set_parent(self);
+
+        imp.notes.borrow_mut().push((note, position));
+        self.queue_allocate();
+    }
+
+    pub fn remove_note(&self, note: &Note) {
+        let mut notes = self.imp().notes.borrow_mut();
+        if let Some(index) = notes.iter().position(|(other, _)| other == note) {
+            let (note, _) = notes.remove(index);
+            note.unparent();
+        }
+    }
+
+    /// Move all nodes back to where they would have been placed by default, discarding any manual layout.
+    ///
+    /// Positions that were requested for nodes that have not appeared yet are discarded as well.
+    pub fn reset_layout(&self) {
+        let imp = self.imp();
+        imp.pending_positions.borrow_mut().clear();
+
+        let mut nodes = imp.nodes.borrow_mut();
+
+        // Place nodes in the order they were added in, so that the result matches a fresh start.
+        let mut ids: Vec<u32> = nodes.keys().copied().collect();
+        ids.sort_unstable();
+
+        let mut placed = Vec::with_capacity(ids.len());
+        for id in ids {
+            let (node, point) = nodes.get_mut(&id).expect("Node disappeared during reset");
+            *point = imp.default_node_position(node.node_type(), placed.iter().copied());
+            placed.push(*point);
+        }
+        drop(nodes);
+
+        self.queue_allocate();
+    }
+
+    /// Get the selected nodes that are not locked in place, with their positions.
+    fn movable_selected_nodes(&self) -> Vec<(Node, Point)> {
+        let imp = self.imp();
+        let nodes = imp.nodes.borrow();
+        imp.selected_nodes
+            .borrow()
+            .iter()
+            .filter_map(|id| nodes.get(id))
+            .filter(|(node, _)| !node.is_locked())
+            .cloned()
+            .collect()
+    }
+
+    /// Move the selected nodes so that their left edges line up with the leftmost one.
+    pub fn align_selected_nodes_left(&self) {
+        let selected = self.movable_selected_nodes();
+        let Some(left) = selected.iter().map(|(_, point)| point.x()).reduce(f32::min) else {
+            return;
+        };
 
-            if let Some(adjustment) = adjustment {
-                adjustment
-                    .connect_value_changed(clone!(@weak obj => move |_|  obj.queue_allocate() ));
-            }
+        for (node, point) in selected {
+            self.move_node(&node, &Point::new(left, point.y()));
         }
+    }
 
-        fn set_adjustment_values(
-            &self,
-            obj: &super::GraphView,
-            adjustment: &gtk::Adjustment,
-            orientation: gtk::Orientation,
-        ) {
-            let size = match orientation {
-                gtk::Orientation::Horizontal => obj.width(),
-                gtk::Orientation::Vertical => obj.height(),
-                _ => unimplemented!(),
-            };
-            let zoom_factor = self.zoom_factor.get();
+    /// Move the selected nodes so that their top edges line up with the topmost one.
+    pub fn align_selected_nodes_top(&self) {
+        let selected = self.movable_selected_nodes();
+        let Some(top) = selected.iter().map(|(_, point)| point.y()).reduce(f32::min) else {
+            return;
+        };
 
-            adjustment.configure(
-                adjustment.value(),
-                -(CANVAS_SIZE / 2.0) * zoom_factor,
-                (CANVAS_SIZE / 2.0) * zoom_factor,
-                (f64::from(size) * 0.1) * zoom_factor,
-                (f64::from(size) * 0.9) * zoom_factor,
-                f64::from(size) * zoom_factor,
-            );
+        for (node, point) in selected {
+            self.move_node(&node, &Point::new(point.x(), top));
         }
     }
-}
 
-glib::wrapper! {
-    pub struct GraphView(ObjectSubclass<imp::GraphView>)
-        @extends gtk::Widget;
-}
+    /// Space the selected nodes evenly in the specified direction.
+    ///
+    /// The first and last node stay in place, and the others are moved so that the gaps between all nodes are equal.
+    pub fn distribute_selected_nodes(&self, orientation: gtk::Orientation) {
+        let horizontal = orientation == gtk::Orientation::Horizontal;
+        // Get the start and size of the node in the direction of distribution.
+        let extent = |node: &Node, point: &Point| {
+            if horizontal {
+                (point.x(), node.width() as f32)
+            } else {
+                (point.y(), node.height() as f32)
+            }
+        };
 
-impl GraphView {
-    pub const ZOOM_MIN: f64 = 0.3;
-    pub const ZOOM_MAX: f64 = 4.0;
+        let mut selected = self.movable_selected_nodes();
+        if selected.len() < 3 {
+            return;
+        }
+        selected.sort_by(|(a, a_point), (b, b_point)| {
+            extent(a, a_point).0.total_cmp(&extent(b, b_point).0)
+        });
 
-    pub fn new() -> Self {
-        glib::Object::new()
-    }
+        let (first_start, _) = extent(&selected[0].0, &selected[0].1);
+        let (last_start, last_size) = extent(
+            &selected[selected.len() - 1].0,
+            &selected[selected.len() - 1].1,
+        );
+        let total_size: f32 = selected
+            .iter()
+            .map(|(node, point)| extent(node, point).1)
+            .sum();
+        let gap = (last_start + last_size - first_start - total_size) / (selected.len() - 1) as f32;
 
-    pub fn zoom_factor(&self) -> f64 {
-        self.property("zoom-factor")
+        let mut start = first_start;
+        for (node, point) in &selected {
+            let position = if horizontal {
+                Point::new(start, point.y())
+            } else {
+                Point::new(point.x(), start)
+            };
+            self.move_node(node, &position);
+            start += extent(node, point).1 + gap;
+        }
     }
 
-    /// Set the scale factor.
-    ///
-    /// A factor of 1.0 is equivalent to 100% zoom, 0.5 to 50% zoom etc.
-    ///
-    /// An optional anchor (in canvas-space coordinates) can be specified, which will be used as the center of the zoom,
-    /// so that its position stays fixed.
-    /// If no anchor is specified, the middle of the screen is used instead.
+    /// Pack the selected nodes into a tidy grid around their common center, so that none of them overlap.
     ///
-    /// Note that the zoom level is [clamped](`f64::clamp`) to between 30% and 300%.
-    /// See [`Self::ZOOM_MIN`] and [`Self::ZOOM_MAX`].
-    pub fn set_zoom_factor(&self, zoom_factor: f64, anchor: Option<(f64, f64)>) {
-        let zoom_factor = zoom_factor.clamp(Self::ZOOM_MIN, Self::ZOOM_MAX);
+    /// Like new nodes, they are stacked in columns in the direction of signal flow, keeping their order.
+    /// Locked nodes stay where they are.
+    pub fn tidy_selected_nodes(&self) {
+        let imp = self.imp();
+
+        let mut selected = self.movable_selected_nodes();
+        if selected.len() < 2 {
+            return;
+        }
+        selected.sort_by(|(_, a), (_, b)| {
+            let (a_x, a_y) = imp.flow_coordinates(a.x(), a.y());
+            let (b_x, b_y) = imp.flow_coordinates(b.x(), b.y());
+            a_x.total_cmp(&b_x).then_with(|| a_y.total_cmp(&b_y))
+        });
 
-        let (anchor_x_screen, anchor_y_screen) = anchor.unwrap_or_else(|| {
+        let count = selected.len() as f32;
+        let (sum_x, sum_y) = selected.iter().fold((0.0, 0.0), |(x, y), (node, point)| {
             (
-                self.allocation().width() as f64 / 2.0,
-                self.allocation().height() as f64 / 2.0,
+                x + point.x() + node.width() as f32 / 2.0,
+                y + point.y() + node.height() as f32 / 2.0,
             )
         });
+        let (center_x, center_y) = imp.flow_coordinates(sum_x / count, sum_y / count);
 
-        let old_zoom = self.imp().zoom_factor.get();
-        let hadjustment_ref = self.imp().hadjustment.borrow();
-        let vadjustment_ref = self.imp().vadjustment.borrow();
-        let hadjustment = hadjustment_ref.as_ref().unwrap();
-        let vadjustment = vadjustment_ref.as_ref().unwrap();
-
-        let x_total = (anchor_x_screen + hadjustment.value()) / old_zoom;
-        let y_total = (anchor_y_screen + vadjustment.value()) / old_zoom;
-
-        let new_hadjustment = x_total * zoom_factor - anchor_x_screen;
-        let new_vadjustment = y_total * zoom_factor - anchor_y_screen;
+        // The nodes are packed in flow space, so that columns become rows for a vertical flow.
+        let sizes: Vec<(f32, f32)> = selected
+            .iter()
+            .map(|(node, _)| imp.flow_coordinates(node.width() as f32, node.height() as f32))
+            .collect();
+        let positions = pack_in_columns(&sizes, Point::new(center_x, center_y));
 
-        hadjustment.set_value(new_hadjustment);
-        vadjustment.set_value(new_vadjustment);
-
-        self.set_property("zoom-factor", zoom_factor);
+        for ((node, _), position) in selected.iter().zip(positions) {
+            let (x, y) = imp.flow_coordinates(position.x(), position.y());
+            self.move_node(node, &Point::new(x, y));
+        }
     }
 
-    pub fn add_node(&self, id: u32, node: Node, node_type: Option<NodeType>) {
-        let imp = self.imp();
-        node.set_parent(self);
+    /// Arrange all nodes in layers by the direction signals flow through their links.
+    ///
+    /// Nodes without incoming links form the first layer, and every other node is placed one layer
+    /// after the furthest node linking to it, so that chains of connected nodes line up in the direction of flow.
+    /// Sinks are placed in the last layer. Within each layer, nodes are ordered by the average position
+    /// of the nodes they are linked to, which reduces the number of crossing links.
+    ///
+    /// Locked nodes are taken into account for the layers, but stay where they are.
+    pub fn auto_layout(&self) {
+        // Space between layers and between nodes of the same layer, in canvas space.
+        const LAYER_SPACING: f32 = 120.0;
+        const NODE_SPACING: f32 = 30.0;
+        // Number of times layers are reordered, alternating between the direction of flow and the reverse.
+        const ORDERING_SWEEPS: usize = 4;
 
-        // Place widgets in colums of 3, growing down
-        let x = if let Some(node_type) = node_type {
-            match node_type {
-                NodeType::Output => 20.0,
-                NodeType::Input => 820.0,
-            }
-        } else {
-            420.0
-        };
+        let imp = self.imp();
 
-        let y = imp
+        // Collect the nodes with their sizes and current positions in flow space.
+        let nodes: BTreeMap<u32, (Node, (f32, f32), (f32, f32))> = imp
             .nodes
             .borrow()
-            .values()
-            .map(|node| {
-                // Map nodes to their locations
-                let point = self.node_position(&node.0.clone().upcast()).unwrap();
-                (point.x(), point.y())
-            })
-            .filter(|(x2, _)| {
-                // Only look for other nodes that have a similar x coordinate
-                (x - x2).abs() < 50.0
-            })
-            .max_by(|y1, y2| {
-                // Get max in column
-                y1.partial_cmp(y2).unwrap_or(Ordering::Equal)
+            .iter()
+            .map(|(id, (node, point))| {
+                let (_, natural_size) = node.preferred_size();
+                let size =
+                    imp.flow_coordinates(natural_size.width() as f32, natural_size.height() as f32);
+                let position = imp.flow_coordinates(point.x(), point.y());
+                (*id, (node.clone(), size, position))
             })
-            .map_or(20_f32, |(_x, y)| y + 120.0);
+            .collect();
+        if nodes.is_empty() {
+            return;
+        }
+
+        let edges: BTreeSet<(u32, u32)> = imp
+            .links
+            .borrow()
+            .values()
+            .map(|(link, _)| (link.node_from, link.node_to))
+            .filter(|(from, to)| from != to && nodes.contains_key(from) && nodes.contains_key(to))
+            .collect();
+        // Look up the links of each node once, so that the layout stays fast on large graphs.
+        let mut predecessors: HashMap<u32, Vec<u32>> = HashMap::new();
+        let mut successors: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (from, to) in &edges {
+            successors.entry(*from).or_default().push(*to);
+            predecessors.entry(*to).or_default().push(*from);
+        }
+        let unlinked = Vec::new();
+        let predecessors = |id: &u32| predecessors.get(id).unwrap_or(&unlinked);
+        let successors = |id: &u32| successors.get(id).unwrap_or(&unlinked);
+
+        // Assign layers in topological order. Cycles are broken by placing the remaining node
+        // with the lowest id as if its unplaced predecessors did not exist.
+        let mut layers: BTreeMap<u32, usize> = BTreeMap::new();
+        let mut unplaced_predecessors: HashMap<u32, usize> = nodes
+            .keys()
+            .map(|id| (*id, predecessors(id).len()))
+            .collect();
+        let mut remaining: BTreeSet<u32> = nodes.keys().copied().collect();
+        let mut ready: BTreeSet<u32> = remaining
+            .iter()
+            .copied()
+            .filter(|id| unplaced_predecessors[id] == 0)
+            .collect();
+        while let Some(next) = ready
+            .iter()
+            .next()
+            .or_else(|| remaining.iter().next())
+            .copied()
+        {
+            ready.remove(&next);
+            remaining.remove(&next);
+
+            let layer = predecessors(&next)
+                .iter()
+                .filter_map(|from| layers.get(from))
+                .map(|layer| layer + 1)
+                .max()
+                .unwrap_or(0);
+            layers.insert(next, layer);
+
+            for to in successors(&next) {
+                let count = unplaced_predecessors.entry(*to).or_default();
+                *count = count.saturating_sub(1);
+                if *count == 0 && remaining.contains(to) {
+                    ready.insert(*to);
+                }
+            }
+        }
+
+        let last_layer = layers.values().copied().max().unwrap_or(0).max(1);
+        for (id, (node, _, _)) in &nodes {
+            if matches!(node.node_type(), Some(NodeType::Input)) && successors(id).is_empty() {
+                layers.insert(*id, last_layer);
+            }
+        }
+
+        // Start with the current order, so that the layout stays close to what the user arranged.
+        let mut ordered: Vec<Vec<u32>> = vec![Vec::new(); last_layer + 1];
+        for (id, layer) in &layers {
+            ordered[*layer].push(*id);
+        }
+        for layer in &mut ordered {
+            layer.sort_by(|a, b| nodes[a].2 .1.total_cmp(&nodes[b].2 .1));
+        }
+
+        for sweep in 0..ORDERING_SWEEPS {
+            let forward = sweep % 2 == 0;
+            let layer_indices: Vec<usize> = if forward {
+                (1..ordered.len()).collect()
+            } else {
+                (0..ordered.len().saturating_sub(1)).rev().collect()
+            };
+
+            for index in layer_indices {
+                let neighbor_layer = if forward { index - 1 } else { index + 1 };
+                let neighbor_positions: HashMap<u32, usize> = ordered[neighbor_layer]
+                    .iter()
+                    .enumerate()
+                    .map(|(position, id)| (*id, position))
+                    .collect();
+
+                // Nodes without links to the neighboring layer keep their current position.
+                let barycenters: HashMap<u32, f32> = ordered[index]
+                    .iter()
+                    .enumerate()
+                    .map(|(position, id)| {
+                        let linked: Vec<usize> = if forward {
+                            predecessors(id)
+                        } else {
+                            successors(id)
+                        }
+                        .iter()
+                        .filter_map(|other| neighbor_positions.get(other).copied())
+                        .collect();
+                        let barycenter = if linked.is_empty() {
+                            position as f32
+                        } else {
+                            linked.iter().sum::<usize>() as f32 / linked.len() as f32
+                        };
+                        (*id, barycenter)
+                    })
+                    .collect();
+
+                ordered[index].sort_by(|a, b| barycenters[a].total_cmp(&barycenters[b]));
+            }
+        }
+
+        // Place the layers next to each other, each as wide as its widest node, and stack the nodes of a layer.
+        let mut positions = Vec::with_capacity(nodes.len());
+        let mut layer_x = 20.0;
+        for layer in &ordered {
+            let mut y = 20.0;
+            let mut layer_width: f32 = 0.0;
+            for id in layer {
+                let (node, (width, height), _) = &nodes[id];
+                if !node.is_locked() {
+                    let (x, y_canvas) = imp.flow_coordinates(layer_x, y);
+                    positions.push((node.clone(), Point::new(x, y_canvas)));
+                }
 
-        imp.nodes.borrow_mut().insert(id, (node, Point::new(x, y)));
+                y += height + NODE_SPACING;
+                layer_width = layer_width.max(*width);
+            }
+            layer_x += layer_width + LAYER_SPACING;
+        }
+
+        // Positions of nodes that have not appeared yet are kept, as they are not part of the layout.
+        for (node, point) in positions {
+            self.move_node(&node, &point);
+        }
+        self.queue_allocate();
     }
 
     pub fn remove_node(&self, id: u32) {
-        let mut nodes = self.imp().nodes.borrow_mut();
-        if let Some((node, _)) = nodes.remove(&id) {
+        let removed = self.imp().nodes.borrow_mut().remove(&id);
+        if let Some((node, position)) = removed {
+            // Streams come and go, e.g. whenever an application starts playing, so put them back where they were.
+            self.imp()
+                .pending_positions
+                .borrow_mut()
+                .insert(node.node_name(), position);
             node.unparent();
+            if self.imp().hovered_node.get() == Some(id) {
+                self.imp().hovered_node.set(None);
+            }
+            self.imp().selected_nodes.borrow_mut().remove(&id);
+            self.emit_by_name::<()>("graph-changed", &[]);
         } else {
             warn!("Tried to remove non-existant node (id={}) from graph", id);
         }
     }
 
+    pub fn set_node_state(&self, node_id: u32, active: bool) {
+        if let Some((node, _)) = self.imp().nodes.borrow().get(&node_id) {
+            node.set_active(active);
+            self.imp().update_node_dimming(node);
+        } else {
+            warn!("Node state changed on unknown node (id={})", node_id);
+        }
+    }
+
+    /// Mark the node with the specified id as the default device of the specified kind,
+    /// unmarking the previous default.
+    pub fn set_default_device(&self, kind: DeviceKind, node_id: Option<u32>) {
+        for (id, (node, _)) in self.imp().nodes.borrow().iter() {
+            if node.device_kind() == Some(kind) {
+                node.set_default(Some(*id) == node_id);
+            }
+        }
+    }
+
     pub fn add_port(&self, node_id: u32, port_id: u32, port: crate::view::port::Port) {
         if let Some((node, _)) = self.imp().nodes.borrow_mut().get_mut(&node_id) {
+            port.set_show_index(self.imp().show_port_indices.get());
             node.add_port(port_id, port);
         } else {
             error!(
@@ -673,11 +4181,17 @@ impl GraphView {
     }
 
     pub fn add_link(&self, link_id: u32, link: crate::PipewireLink, active: bool) {
+        let nodes = [link.node_from, link.node_to];
         self.imp()
             .links
             .borrow_mut()
             .insert(link_id, (link, active));
+        self.imp().update_connected_ports(&nodes);
+        if self.imp().focused_port.get().is_some() {
+            self.update_focus_styles();
+        }
         self.queue_draw();
+        self.emit_by_name::<()>("graph-changed", &[]);
     }
 
     pub fn set_link_state(&self, link_id: u32, active: bool) {
@@ -689,13 +4203,221 @@ impl GraphView {
         }
     }
 
-    pub fn remove_link(&self, id: u32) {
-        let mut links = self.imp().links.borrow_mut();
-        links.remove(&id);
+    /// Remove the link with the specified id, returning it if it exists.
+    pub fn remove_link(&self, id: u32) -> Option<crate::PipewireLink> {
+        let removed = self.imp().links.borrow_mut().remove(&id);
+        if let Some((ref link, _)) = removed {
+            self.imp()
+                .update_connected_ports(&[link.node_from, link.node_to]);
+        }
+        if self.imp().focused_port.get().is_some() {
+            self.update_focus_styles();
+        }
+
+        self.queue_draw();
+        if removed.is_some() {
+            self.emit_by_name::<()>("graph-changed", &[]);
+        }
+
+        removed.map(|(link, _)| link)
+    }
+
+    /// Find the id of a port by its name, its direction and the `node.name` of its node.
+    ///
+    /// If several nodes have the same name, the first one with a matching port is used.
+    pub fn port_id_by_name(
+        &self,
+        node_name: &str,
+        port_name: &str,
+        direction: Direction,
+    ) -> Option<u32> {
+        self.imp()
+            .nodes
+            .borrow()
+            .values()
+            .filter(|(node, _)| node.node_name() == node_name)
+            .find_map(|(node, _)| node.port_by_name(port_name, direction))
+            .map(|port| port.pipewire_id())
+    }
+
+    /// Find the id of a port of the node with the specified id by the ports name and direction.
+    pub fn port_id_on_node(
+        &self,
+        node_id: u32,
+        port_name: &str,
+        direction: Direction,
+    ) -> Option<u32> {
+        self.imp()
+            .nodes
+            .borrow()
+            .get(&node_id)
+            .and_then(|(node, _)| node.port_by_name(port_name, direction))
+            .map(|port| port.pipewire_id())
+    }
+
+    pub fn focused_port(&self) -> Option<u32> {
+        self.imp().focused_port.get()
+    }
+
+    /// Focus the specified port, so that only links connected to it are drawn and the ports it is connected to stand out.
+    ///
+    /// Passing `None` clears the focus and shows all links again.
+    pub fn set_focused_port(&self, port_id: Option<u32>) {
+        self.imp().focused_port.set(port_id);
+        self.update_focus_styles();
+        self.queue_draw();
+    }
+
+    /// Move the `focused` and `focus-peer` style classes to the currently focused port and the ports linked to it.
+    fn update_focus_styles(&self) {
+        let imp = self.imp();
+        let nodes = imp.nodes.borrow();
+        let find_port = |port_id: u32| nodes.values().find_map(|(node, _)| node.get_port(port_id));
+
+        for port in imp
+            .focus_styled_ports
+            .take()
+            .into_iter()
+            .filter_map(find_port)
+        {
+            port.remove_css_class("focused");
+            port.remove_css_class("focus-peer");
+        }
+
+        let Some(focused_port) = imp.focused_port.get() else { return };
+
+        let peers: HashSet<u32> = imp
+            .links
+            .borrow()
+            .values()
+            .filter_map(|(link, _)| {
+                if link.port_from == focused_port {
+                    Some(link.port_to)
+                } else if link.port_to == focused_port {
+                    Some(link.port_from)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        for port in peers.iter().copied().filter_map(find_port) {
+            port.add_css_class("focus-peer");
+        }
+        if let Some(port) = find_port(focused_port) {
+            port.add_css_class("focused");
+        }
+
+        let mut styled = imp.focus_styled_ports.borrow_mut();
+        styled.extend(peers);
+        styled.insert(focused_port);
+    }
+
+    /// Get the names of the favorite nodes in alphabetical order,
+    /// together with the id of the node with that name if it is currently on the graph.
+    pub fn favorites(&self) -> Vec<(String, Option<u32>)> {
+        let nodes = self.imp().nodes.borrow();
+
+        self.imp()
+            .favorites
+            .borrow()
+            .iter()
+            .map(|name| {
+                let id = nodes
+                    .iter()
+                    .find(|(_, (node, _))| node.name() == *name)
+                    .map(|(id, _)| *id);
+                (name.clone(), id)
+            })
+            .collect()
+    }
+
+    /// Get the `node.name` of the node with the specified id.
+    pub fn node_name(&self, id: u32) -> Option<String> {
+        self.imp()
+            .nodes
+            .borrow()
+            .get(&id)
+            .map(|(node, _)| node.node_name())
+    }
+
+    /// Get the `node.name` of the node with the specified id and the name of its port with the specified id.
+    pub fn port_names(&self, node_id: u32, port_id: u32) -> Option<(String, String)> {
+        let nodes = self.imp().nodes.borrow();
+        let (node, _) = nodes.get(&node_id)?;
+        let port = node.get_port(port_id)?;
+
+        Some((node.node_name(), port.name()))
+    }
+
+    /// Start drawing a link from the port with the specified id to the pointer, while it is dragged out.
+    pub fn begin_link_preview(&self, port_id: u32, x: f64, y: f64) {
+        self.imp().link_preview.set(Some((port_id, (x, y))));
+        self.queue_draw();
+    }
 
+    /// Stop drawing the link that was dragged out of a port, e.g. because it was dropped.
+    pub fn end_link_preview(&self) {
+        self.imp().link_preview.set(None);
         self.queue_draw();
     }
 
+    /// Get all links from or to the node with the specified id.
+    pub fn links_for_node(&self, node_id: u32) -> Vec<crate::PipewireLink> {
+        self.imp()
+            .links
+            .borrow()
+            .values()
+            .filter(|(link, _)| link.node_from == node_id || link.node_to == node_id)
+            .map(|(link, _)| link.clone())
+            .collect()
+    }
+
+    /// Whether there is a link between the two specified ports.
+    pub fn has_link(&self, port_from: u32, port_to: u32) -> bool {
+        self.imp()
+            .links
+            .borrow()
+            .values()
+            .any(|(link, _)| link.port_from == port_from && link.port_to == port_to)
+    }
+
+    /// Mark the two ports of a requested link as waiting for the link to be created or removed.
+    pub fn set_link_pending(&self, port_from: u32, port_to: u32, pending: bool) {
+        for (node, _) in self.imp().nodes.borrow().values() {
+            for port in [port_from, port_to]
+                .iter()
+                .filter_map(|port_id| node.get_port(*port_id))
+            {
+                if pending {
+                    port.add_css_class("pending");
+                } else {
+                    port.remove_css_class("pending");
+                }
+            }
+        }
+    }
+
+    /// Get the number of nodes currently on the graph.
+    pub fn node_count(&self) -> usize {
+        self.imp().nodes.borrow().len()
+    }
+
+    /// Get the number of ports of all nodes currently on the graph.
+    pub fn port_count(&self) -> usize {
+        self.imp()
+            .nodes
+            .borrow()
+            .values()
+            .map(|(node, _)| node.port_count())
+            .sum()
+    }
+
+    /// Get the number of links currently on the graph.
+    pub fn link_count(&self) -> usize {
+        self.imp().links.borrow().len()
+    }
+
     /// Get the position of the specified node inside the graphview.
     ///
     /// The returned position is in canvas-space (non-zoomed, (0, 0) fixed in the middle of the canvas).
@@ -707,6 +4429,33 @@ impl GraphView {
             .map(|(_, point)| *point)
     }
 
+    /// Move the node with the specified `node.name` to a position in canvas-space.
+    ///
+    /// If no node with that name is currently on the graph, a warning is logged and the position is remembered
+    /// and applied once a node with that name is added.
+    pub fn set_node_position_by_name(&self, name: &str, point: Point) {
+        let node = self
+            .imp()
+            .nodes
+            .borrow()
+            .values()
+            .find(|(node, _)| node.node_name() == name)
+            .map(|(node, _)| node.clone());
+
+        if let Some(node) = node {
+            self.move_node(&node, &point);
+        } else {
+            warn!(
+                "Tried to set position of node (name={}) that is not on the graph yet, deferring it",
+                name
+            );
+            self.imp()
+                .pending_positions
+                .borrow_mut()
+                .insert(name.to_string(), point);
+        }
+    }
+
     pub(super) fn move_node(&self, widget: &Node, point: &Point) {
         let mut nodes = self.imp().nodes.borrow_mut();
         let mut node = nodes
@@ -714,7 +4463,7 @@ impl GraphView {
             .expect("Node is not on the graph");
 
         // Clamp the new position to within the graph, so a node can't be moved outside it and be lost.
-        node.1 = Point::new(
+        let position = Point::new(
             point.x().clamp(
                 -(CANVAS_SIZE / 2.0) as f32,
                 (CANVAS_SIZE / 2.0) as f32 - widget.width() as f32,
@@ -724,8 +4473,20 @@ impl GraphView {
                 (CANVAS_SIZE / 2.0) as f32 - widget.height() as f32,
             ),
         );
+        node.1 = position;
+        // Handlers may look at the nodes, so they must not be borrowed while emitting.
+        drop(nodes);
 
-        self.queue_allocate();
+        // The position is stored right away, only placing the node on screen waits for the next frame.
+        self.imp().queue_allocate_once();
+        self.emit_by_name::<()>(
+            "node-moved",
+            &[
+                &widget.pipewire_id(),
+                &f64::from(position.x()),
+                &f64::from(position.y()),
+            ],
+        );
     }
 }
 
@@ -734,3 +4495,117 @@ impl Default for GraphView {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_position_is_applied_once() {
+        let mut pending_positions = HashMap::new();
+        pending_positions.insert("Firefox".to_string(), Point::new(100.0, 50.0));
+        let default = || Point::new(0.0, 0.0);
+
+        let position = take_initial_position(&mut pending_positions, "Firefox", default);
+        assert_eq!((position.x(), position.y()), (100.0, 50.0));
+        assert!(pending_positions.is_empty());
+
+        let position = take_initial_position(&mut pending_positions, "Firefox", default);
+        assert_eq!((position.x(), position.y()), (0.0, 0.0));
+    }
+
+    #[test]
+    fn pending_positions_of_other_nodes_are_kept() {
+        let mut pending_positions = HashMap::new();
+        pending_positions.insert("Firefox".to_string(), Point::new(100.0, 50.0));
+
+        let position =
+            take_initial_position(&mut pending_positions, "mpv", || Point::new(-20.0, 10.0));
+        assert_eq!((position.x(), position.y()), (-20.0, 10.0));
+        assert!(pending_positions.contains_key("Firefox"));
+    }
+
+    #[test]
+    fn inactive_link_dash_scales_with_zoom() {
+        assert_eq!(imp::GraphView::inactive_link_dash(1.0), [10.0, 5.0]);
+        assert_eq!(imp::GraphView::inactive_link_dash(0.5), [5.0, 2.5]);
+        assert_eq!(imp::GraphView::inactive_link_dash(2.0), [20.0, 10.0]);
+    }
+
+    #[test]
+    fn link_with_coincident_ends_is_drawn_as_loop() {
+        let end = (120.0, -40.0);
+        let ((center_x, center_y), radius) = link_loop(end, end, 1.0).unwrap();
+
+        assert!(center_x.is_finite() && center_y.is_finite() && radius.is_finite());
+        assert!(radius > 0.0);
+        // The loop passes through the ports, so it stays attached to them.
+        assert!((f64::hypot(end.0 - center_x, end.1 - center_y) - radius).abs() < 1e-9);
+    }
+
+    #[test]
+    fn loop_scales_with_zoom() {
+        let (_, radius) = link_loop((0.0, 0.0), (0.0, 0.0), 2.0).unwrap();
+        assert_eq!(radius, 2.0 * NORMAL_LOOP_RADIUS);
+    }
+
+    #[test]
+    fn distant_link_ends_are_drawn_as_curve() {
+        assert!(link_loop((0.0, 0.0), (200.0, 30.0), 1.0).is_none());
+        // Ends that are close at a high zoom factor are far apart at a low one.
+        assert!(link_loop((0.0, 0.0), (15.0, 0.0), 2.0).is_some());
+        assert!(link_loop((0.0, 0.0), (15.0, 0.0), 1.0).is_none());
+    }
+
+    #[test]
+    fn packed_nodes_do_not_overlap() {
+        let sizes = [
+            (120.0, 80.0),
+            (200.0, 40.0),
+            (90.0, 150.0),
+            (160.0, 60.0),
+            (100.0, 100.0),
+        ];
+        let positions = pack_in_columns(&sizes, Point::new(300.0, -50.0));
+        assert_eq!(positions.len(), sizes.len());
+
+        let rects: Vec<graphene::Rect> = positions
+            .iter()
+            .zip(sizes)
+            .map(|(point, (width, height))| {
+                graphene::Rect::new(point.x(), point.y(), width, height)
+            })
+            .collect();
+        for (i, a) in rects.iter().enumerate() {
+            for b in &rects[i + 1..] {
+                let overlaps = a.x() < b.x() + b.width()
+                    && b.x() < a.x() + a.width()
+                    && a.y() < b.y() + b.height()
+                    && b.y() < a.y() + a.height();
+                assert!(!overlaps, "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn packed_nodes_are_centered() {
+        let positions = pack_in_columns(&[(100.0, 50.0); 4], Point::new(0.0, 0.0));
+
+        // Two columns of two nodes, with the gap between them in the middle.
+        let left = positions
+            .iter()
+            .map(|point| point.x())
+            .fold(f32::MAX, f32::min);
+        let top = positions
+            .iter()
+            .map(|point| point.y())
+            .fold(f32::MAX, f32::min);
+        assert_eq!(left, -(100.0 + PACKING_SPACING / 2.0));
+        assert_eq!(top, -(50.0 + PACKING_SPACING / 2.0));
+    }
+
+    #[test]
+    fn packing_nothing_gives_no_positions() {
+        assert!(pack_in_columns(&[], Point::new(10.0, 10.0)).is_empty());
+    }
+}
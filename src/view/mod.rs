@@ -18,12 +18,18 @@
 //!
 //! This module contains gtk widgets needed to present the graphical user interface.
 
+mod command_palette;
+mod frame;
 mod graph_view;
 mod node;
+mod note;
 mod port;
 mod zoomentry;
 
+pub use command_palette::{Command, CommandPalette};
+pub use frame::Frame;
 pub use graph_view::GraphView;
 pub use node::Node;
+pub use note::Note;
 pub use port::Port;
 pub use zoomentry::ZoomEntry;
@@ -14,13 +14,27 @@
 //
 // SPDX-License-Identifier: GPL-3.0-only
 
-use gtk::{glib, prelude::*, subclass::prelude::*};
+use gtk::{gdk, gio, glib, prelude::*, subclass::prelude::*};
 use pipewire::spa::Direction;
 
-use std::collections::HashMap;
+use crate::{DeviceKind, NodeType};
+
+use std::collections::{HashMap, HashSet};
+
+/// Compare the ids of the ports a node has with the ids of the ports it should have.
+///
+/// Returns the ids of ports that need to be removed and of ports that need to be added, both in ascending order.
+fn port_changes(current: &HashSet<u32>, new: &HashSet<u32>) -> (Vec<u32>, Vec<u32>) {
+    let mut removed: Vec<u32> = current.difference(new).copied().collect();
+    let mut added: Vec<u32> = new.difference(current).copied().collect();
+    removed.sort_unstable();
+    added.sort_unstable();
+
+    (removed, added)
+}
 
 mod imp {
-    use glib::ParamFlags;
+    use glib::{subclass::Signal, ParamFlags};
     use once_cell::sync::Lazy;
 
     use super::*;
@@ -31,9 +45,40 @@ mod imp {
         pub(super) pipewire_id: Cell<u32>,
         pub(super) grid: gtk::Grid,
         pub(super) label: gtk::Label,
+        /// The title bar of the node, containing the collapse button, the label and the lock button.
+        pub(super) header: gtk::Box,
+        pub(super) collapse_button: gtk::ToggleButton,
+        pub(super) lock_button: gtk::ToggleButton,
+        pub(super) name: RefCell<String>,
+        pub(super) media_name: RefCell<Option<String>>,
         pub(super) ports: RefCell<HashMap<u32, crate::view::port::Port>>,
         pub(super) num_ports_in: Cell<i32>,
         pub(super) num_ports_out: Cell<i32>,
+        /// Whether the node is currently running.
+        pub(super) active: Cell<bool>,
+        /// The kind of device this node is, if it can be made a default device.
+        pub(super) device_kind: Cell<Option<DeviceKind>>,
+        /// Whether the node is a source or sink of data, which decides where it is placed by default.
+        pub(super) node_type: Cell<Option<NodeType>>,
+        /// Name of the application the node belongs to, which is used to group nodes on the graph.
+        pub(super) application_name: RefCell<Option<String>>,
+        /// The `node.name` of the node, which is used to find it by name.
+        pub(super) node_name: RefCell<String>,
+        /// The direction signals flow through the node, which decides on which edges the ports are placed.
+        pub(super) orientation: Cell<gtk::Orientation>,
+        /// Model of the context menu, which shows the node name as its header.
+        pub(super) menu: gio::Menu,
+        pub(super) context_menu: gtk::PopoverMenu,
+        /// The number of connected input and output ports, shown in the nodes tooltip.
+        pub(super) connected_ports: Cell<(usize, usize)>,
+        /// Whether the node is drawn as a simple block without its ports, because the view is zoomed out far.
+        pub(super) simplified: Cell<bool>,
+        /// Whether the user marked the node as a favorite, so that it can be jumped to quickly.
+        pub(super) favorite: Cell<bool>,
+        /// Whether the user collapsed the node to its title bar, hiding its ports.
+        pub(super) collapsed: Cell<bool>,
+        /// Whether the user locked the node in place, so that it can not be moved.
+        pub(super) locked: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -44,6 +89,23 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.set_layout_manager_type::<gtk::BinLayout>();
+            klass.set_css_name("node");
+
+            klass.install_action("node.set-default", None, |node, _, _| {
+                node.emit_by_name::<()>("set-default", &[]);
+            });
+            klass.install_property_action("node.favorite", "favorite");
+            klass.install_property_action("node.collapsed", "collapsed");
+            klass.install_property_action("node.locked", "locked");
+            klass.install_action("node.disconnect-all", None, |node, _, _| {
+                node.emit_by_name::<()>("disconnect-all", &[]);
+            });
+            klass.install_action("node.copy-connections", None, |node, _, _| {
+                node.emit_by_name::<()>("copy-connections", &[]);
+            });
+            klass.install_action("node.paste-connections", None, |node, _, _| {
+                node.emit_by_name::<()>("paste-connections", &[]);
+            });
         }
 
         fn new() -> Self {
@@ -54,19 +116,60 @@ mod imp {
             label.set_lines(2);
             label.set_max_width_chars(20);
             label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+            label.set_hexpand(true);
+
+            let collapse_button = gtk::ToggleButton::new();
+            collapse_button.set_icon_name("pan-down-symbolic");
+            collapse_button.set_tooltip_text(Some("Collapse Ports"));
+            collapse_button.set_valign(gtk::Align::Center);
+            collapse_button.add_css_class("flat");
+
+            let lock_button = gtk::ToggleButton::new();
+            lock_button.set_icon_name("changes-allow-symbolic");
+            lock_button.set_tooltip_text(Some("Lock Position"));
+            lock_button.set_valign(gtk::Align::Center);
+            lock_button.add_css_class("flat");
 
-            grid.attach(&label, 0, 0, 2, 1);
+            let header = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+            header.append(&collapse_button);
+            header.append(&label);
+            header.append(&lock_button);
+
+            grid.attach(&header, 0, 0, 2, 1);
 
             // Display a grab cursor when the mouse is over the label so the user knows the node can be dragged.
             label.set_cursor(gtk::gdk::Cursor::from_name("grab", None).as_ref());
 
+            // The menu is filled once the node has a name.
+            let menu = gio::Menu::new();
+            let context_menu = gtk::PopoverMenu::from_model(Some(&menu));
+            context_menu.set_has_arrow(false);
+
             Self {
                 pipewire_id: Cell::new(0),
                 grid,
                 label,
+                header,
+                collapse_button,
+                lock_button,
+                name: RefCell::new(String::new()),
+                media_name: RefCell::new(None),
                 ports: RefCell::new(HashMap::new()),
                 num_ports_in: Cell::new(0),
                 num_ports_out: Cell::new(0),
+                active: Cell::new(true),
+                device_kind: Cell::new(None),
+                node_type: Cell::new(None),
+                application_name: RefCell::new(None),
+                node_name: RefCell::new(String::new()),
+                orientation: Cell::new(gtk::Orientation::Horizontal),
+                menu,
+                context_menu,
+                connected_ports: Cell::new((0, 0)),
+                simplified: Cell::new(false),
+                favorite: Cell::new(false),
+                collapsed: Cell::new(false),
+                locked: Cell::new(false),
             }
         }
     }
@@ -74,7 +177,74 @@ mod imp {
     impl ObjectImpl for Node {
         fn constructed(&self) {
             self.parent_constructed();
-            self.grid.set_parent(&*self.obj());
+
+            let obj = self.obj();
+            self.grid.set_parent(&*obj);
+            self.context_menu.set_parent(&*obj);
+
+            // Nodes can be focused, so that they can be moved around with the keyboard.
+            obj.set_focusable(true);
+
+            obj.bind_property("collapsed", &self.collapse_button, "active")
+                .bidirectional()
+                .sync_create()
+                .build();
+            obj.bind_property("locked", &self.lock_button, "active")
+                .bidirectional()
+                .sync_create()
+                .build();
+
+            // Nodes are not a default device until told otherwise.
+            obj.action_set_enabled("node.set-default", false);
+
+            let click_controller = gtk::GestureClick::new();
+            click_controller.set_button(gdk::BUTTON_SECONDARY);
+            click_controller.connect_pressed(|click_controller, _, x, y| {
+                let node = click_controller.widget().downcast::<super::Node>().unwrap();
+
+                let context_menu = &node.imp().context_menu;
+                context_menu.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+                context_menu.popup();
+            });
+            obj.add_controller(click_controller);
+
+            // Double-clicking the title bar scrolls the node to the center of the view.
+            let title_click_controller = gtk::GestureClick::new();
+            title_click_controller.set_button(gdk::BUTTON_PRIMARY);
+            title_click_controller.connect_pressed(
+                glib::clone!(@weak obj => move |_, n_press, _, _| {
+                    if n_press != 2 {
+                        return;
+                    }
+                    if let Some(graphview) = obj.graphview() {
+                        graphview.center_on_node(obj.pipewire_id());
+                    }
+                }),
+            );
+            self.header.add_controller(title_click_controller);
+
+            // The label and ports have their own tooltips, so this is only shown for the rest of the node,
+            // such as the space around the port columns.
+            obj.set_has_tooltip(true);
+            obj.connect_query_tooltip(|node, x, y, _, tooltip| {
+                // Ports only show a tooltip when their name is cut off, and should not show this one otherwise.
+                let on_port = node
+                    .pick(f64::from(x), f64::from(y), gtk::PickFlags::DEFAULT)
+                    .and_then(|target| target.ancestor(crate::view::port::Port::static_type()))
+                    .is_some();
+                if on_port {
+                    return false;
+                }
+
+                let (inputs, outputs) = node.imp().connected_ports.get();
+                tooltip.set_text(Some(&format!(
+                    "{} connected, {} connected",
+                    count_ports(inputs, "input"),
+                    count_ports(outputs, "output")
+                )));
+
+                true
+            });
         }
 
         fn properties() -> &'static [glib::ParamSpec] {
@@ -84,6 +254,16 @@ mod imp {
                         .flags(ParamFlags::READWRITE | ParamFlags::CONSTRUCT_ONLY)
                         .build(),
                     glib::ParamSpecString::builder("name").build(),
+                    glib::ParamSpecString::builder("media-name").build(),
+                    glib::ParamSpecBoolean::builder("favorite")
+                        .default_value(false)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("collapsed")
+                        .default_value(false)
+                        .build(),
+                    glib::ParamSpecBoolean::builder("locked")
+                        .default_value(false)
+                        .build(),
                 ]
             });
 
@@ -93,7 +273,11 @@ mod imp {
         fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
             match pspec.name() {
                 "pipewire-id" => self.pipewire_id.get().to_value(),
-                "name" => self.label.text().to_value(),
+                "name" => self.name.borrow().to_value(),
+                "media-name" => self.media_name.borrow().to_value(),
+                "favorite" => self.favorite.get().to_value(),
+                "collapsed" => self.collapsed.get().to_value(),
+                "locked" => self.locked.get().to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -101,20 +285,134 @@ mod imp {
         fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
             match pspec.name() {
                 "name" => {
-                    self.label.set_text(value.get().unwrap());
-                    self.label.set_tooltip_text(value.get().ok());
+                    *self.name.borrow_mut() = value.get().unwrap();
+                    self.update_label();
+                }
+                "media-name" => {
+                    *self.media_name.borrow_mut() = value.get().unwrap();
+                    self.update_label();
                 }
                 "pipewire-id" => self.pipewire_id.set(value.get().unwrap()),
+                "favorite" => self.favorite.set(value.get().unwrap()),
+                "collapsed" => {
+                    self.collapsed.set(value.get().unwrap());
+                    self.update_port_visibility();
+                }
+                "locked" => {
+                    let locked = value.get().unwrap();
+                    self.locked.set(locked);
+                    self.lock_button.set_icon_name(if locked {
+                        "changes-prevent-symbolic"
+                    } else {
+                        "changes-allow-symbolic"
+                    });
+                }
                 _ => unimplemented!(),
             }
         }
 
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+                vec![
+                    Signal::builder("set-default").build(),
+                    Signal::builder("disconnect-all").build(),
+                    Signal::builder("copy-connections").build(),
+                    Signal::builder("paste-connections").build(),
+                ]
+            });
+
+            SIGNALS.as_ref()
+        }
+
         fn dispose(&self) {
             self.grid.unparent();
+            self.context_menu.unparent();
+        }
+    }
+
+    impl WidgetImpl for Node {
+        fn size_allocate(&self, width: i32, height: i32, baseline: i32) {
+            self.parent_size_allocate(width, height, baseline);
+            self.context_menu.present();
         }
     }
 
-    impl WidgetImpl for Node {}
+    /// Format a number of ports of some kind, e.g. "1 input" or "3 outputs".
+    fn count_ports(count: usize, kind: &str) -> String {
+        if count == 1 {
+            format!("{} {}", count, kind)
+        } else {
+            format!("{} {}s", count, kind)
+        }
+    }
+
+    impl Node {
+        /// Update the title label from the name and media name.
+        ///
+        /// If the node has a media name, the name is shown in bold, with the media name in a smaller font below it.
+        fn update_label(&self) {
+            let name = self.name.borrow();
+
+            // Property values come from pipewire clients, so they need to be escaped to avoid markup injection.
+            match self.media_name.borrow().as_deref() {
+                Some(media_name) => {
+                    self.label.set_markup(&format!(
+                        "<b>{}</b>\n<small>{}</small>",
+                        glib::markup_escape_text(&name),
+                        glib::markup_escape_text(media_name)
+                    ));
+                    self.label
+                        .set_tooltip_text(Some(&format!("{}\n{}", name, media_name)));
+                }
+                None => {
+                    self.label.set_text(&name);
+                    self.label.set_tooltip_text(Some(&name));
+                }
+            }
+
+            self.update_menu(&name);
+        }
+
+        /// Fill the context menu, with the node name as a header that can not be clicked.
+        fn update_menu(&self, name: &str) {
+            let section = gio::Menu::new();
+            let set_default = gio::MenuItem::new(Some("Set as Default"), Some("node.set-default"));
+            // Only show the item for nodes that can actually be made the default.
+            set_default.set_attribute_value("hidden-when", Some(&"action-disabled".to_variant()));
+            section.append_item(&set_default);
+            section.append(Some("Favorite"), Some("node.favorite"));
+            section.append(Some("Collapse Ports"), Some("node.collapsed"));
+            section.append(Some("Lock Position"), Some("node.locked"));
+            section.append(Some("Disconnect All"), Some("node.disconnect-all"));
+            section.append(Some("Copy Connections"), Some("node.copy-connections"));
+            section.append(Some("Paste Connections"), Some("node.paste-connections"));
+
+            self.menu.remove_all();
+            self.menu.append_section(Some(name), &section);
+        }
+
+        /// Show the ports only if the node is neither simplified nor collapsed.
+        pub(super) fn update_port_visibility(&self) {
+            let obj = self.obj();
+            let collapsed = self.collapsed.get();
+            let hidden = collapsed || self.simplified.get();
+
+            for port in self.ports.borrow().values() {
+                port.set_visible(!hidden);
+            }
+
+            self.collapse_button.set_icon_name(if collapsed {
+                "pan-end-symbolic"
+            } else {
+                "pan-down-symbolic"
+            });
+            if collapsed {
+                obj.add_css_class("collapsed");
+            } else {
+                obj.remove_css_class("collapsed");
+            }
+        }
+    }
 }
 
 glib::wrapper! {
@@ -123,9 +421,10 @@ glib::wrapper! {
 }
 
 impl Node {
-    pub fn new(name: &str, pipewire_id: u32) -> Self {
+    pub fn new(name: &str, media_name: Option<&str>, pipewire_id: u32) -> Self {
         glib::Object::builder()
             .property("name", &name)
+            .property("media-name", media_name)
             .property("pipewire-id", &pipewire_id)
             .build()
     }
@@ -134,6 +433,12 @@ impl Node {
         self.property("pipewire-id")
     }
 
+    /// Get the graph view the node is shown on.
+    fn graphview(&self) -> Option<super::GraphView> {
+        self.ancestor(super::GraphView::static_type())
+            .and_then(|graphview| graphview.downcast().ok())
+    }
+
     /// Get the nodes `name` property, which represents the displayed name.
     pub fn name(&self) -> String {
         self.property("name")
@@ -144,21 +449,264 @@ impl Node {
         self.set_property("name", name);
     }
 
+    /// Whether the node is currently running, i.e. processing data.
+    pub fn is_active(&self) -> bool {
+        self.imp().active.get()
+    }
+
+    pub fn set_active(&self, active: bool) {
+        self.imp().active.set(active);
+    }
+
+    /// The kind of device this node is, if it can be made a default device.
+    pub fn device_kind(&self) -> Option<DeviceKind> {
+        self.imp().device_kind.get()
+    }
+
+    /// Set the kind of device this node is, which also controls whether "Set as Default" is offered.
+    pub fn set_device_kind(&self, device_kind: Option<DeviceKind>) {
+        self.imp().device_kind.set(device_kind);
+        self.action_set_enabled("node.set-default", device_kind.is_some());
+    }
+
+    pub fn node_type(&self) -> Option<NodeType> {
+        self.imp().node_type.get()
+    }
+
+    pub fn set_node_type(&self, node_type: Option<NodeType>) {
+        self.imp().node_type.set(node_type);
+    }
+
+    /// The `node.name` of the node.
+    ///
+    /// Unlike the displayed name, this does not change with the description of the node,
+    /// so it is used to recognize the node, e.g. when it reappears.
+    pub fn node_name(&self) -> String {
+        self.imp().node_name.borrow().clone()
+    }
+
+    pub fn set_node_name(&self, node_name: &str) {
+        *self.imp().node_name.borrow_mut() = node_name.to_string();
+    }
+
+    /// Name of the application the node belongs to, if it reported one.
+    pub fn application_name(&self) -> Option<String> {
+        self.imp().application_name.borrow().clone()
+    }
+
+    pub fn set_application_name(&self, application_name: Option<&str>) {
+        *self.imp().application_name.borrow_mut() = application_name.map(String::from);
+    }
+
+    /// Whether the user marked the node as a favorite.
+    pub fn is_favorite(&self) -> bool {
+        self.property("favorite")
+    }
+
+    pub fn set_favorite(&self, favorite: bool) {
+        self.set_property("favorite", favorite);
+    }
+
+    /// Whether the node is collapsed to its title bar, hiding its ports.
+    pub fn is_collapsed(&self) -> bool {
+        self.property("collapsed")
+    }
+
+    pub fn set_collapsed(&self, collapsed: bool) {
+        self.set_property("collapsed", collapsed);
+    }
+
+    /// Whether the node is locked in place, so that it can not be moved by the user.
+    pub fn is_locked(&self) -> bool {
+        self.property("locked")
+    }
+
+    pub fn set_locked(&self, locked: bool) {
+        self.set_property("locked", locked);
+    }
+
+    /// Whether the ports of the node are hidden, because it is simplified or collapsed.
+    pub fn ports_hidden(&self) -> bool {
+        let imp = self.imp();
+        imp.simplified.get() || imp.collapsed.get()
+    }
+
+    /// Set how many input and output ports of the node are connected, which is shown in the nodes tooltip.
+    pub fn set_connected_ports(&self, inputs: usize, outputs: usize) {
+        self.imp().connected_ports.set((inputs, outputs));
+    }
+
+    /// Mark the node as being the default device of its kind.
+    pub fn set_default(&self, default: bool) {
+        if default {
+            self.add_css_class("default");
+        } else {
+            self.remove_css_class("default");
+        }
+    }
+
+    /// Set the direction signals flow through the node.
+    ///
+    /// With a horizontal orientation, inputs are placed on the left edge and outputs on the right edge.
+    /// With a vertical orientation, inputs are placed on the top edge and outputs on the bottom edge.
+    pub fn set_orientation(&self, orientation: gtk::Orientation) {
+        let imp = self.imp();
+
+        if imp.orientation.get() == orientation {
+            return;
+        }
+        imp.orientation.set(orientation);
+
+        self.relayout_ports();
+    }
+
+    /// Lay out all ports again, keeping them in the order they were added in.
+    fn relayout_ports(&self) {
+        let imp = self.imp();
+
+        let mut ports: Vec<_> = imp
+            .ports
+            .borrow()
+            .iter()
+            .map(|(id, port)| (*id, port.clone()))
+            .collect();
+        ports.sort_unstable_by_key(|(id, _)| *id);
+
+        for (_, port) in &ports {
+            imp.grid.remove(port);
+        }
+        imp.num_ports_in.set(0);
+        imp.num_ports_out.set(0);
+
+        for (_, port) in &ports {
+            self.attach_port(port);
+        }
+        self.attach_label();
+    }
+
     pub fn add_port(&mut self, id: u32, port: super::port::Port) {
+        self.attach_port(&port);
+        self.attach_label();
+
+        self.imp().ports.borrow_mut().insert(id, port);
+    }
+
+    /// Replace the nodes ports with the specified set of ports.
+    ///
+    /// Only ports that are not on the node yet are added, and only ports missing from `new_ports` are removed.
+    /// Ports that are already on the node are kept as they are, so they retain their state.
+    pub fn update_ports(&self, new_ports: Vec<super::port::Port>) {
+        let imp = self.imp();
+
+        let mut new_ports: HashMap<u32, super::port::Port> = new_ports
+            .into_iter()
+            .map(|port| (port.pipewire_id(), port))
+            .collect();
+        let current_ids: HashSet<u32> = imp.ports.borrow().keys().copied().collect();
+        let (removed_ids, added_ids) =
+            port_changes(&current_ids, &new_ports.keys().copied().collect());
+
+        for id in removed_ids {
+            if let Some(port) = imp.ports.borrow_mut().remove(&id) {
+                port.unparent();
+            }
+        }
+
+        for id in added_ids {
+            if let Some(port) = new_ports.remove(&id) {
+                self.attach_port(&port);
+                imp.ports.borrow_mut().insert(id, port);
+            }
+        }
+
+        // Removed ports leave gaps in the grid, so lay out everything again.
+        self.relayout_ports();
+    }
+
+    /// Whether the node is drawn as a simple block without its ports.
+    pub fn is_simplified(&self) -> bool {
+        self.imp().simplified.get()
+    }
+
+    /// Draw the node as a simple block with only its title, or with all its ports again.
+    pub fn set_simplified(&self, simplified: bool) {
+        let imp = self.imp();
+        if imp.simplified.replace(simplified) == simplified {
+            return;
+        }
+
+        imp.update_port_visibility();
+        if simplified {
+            self.add_css_class("simplified");
+        } else {
+            self.remove_css_class("simplified");
+        }
+    }
+
+    /// Attach the port to the grid after the other ports with the same direction.
+    fn attach_port(&self, port: &super::port::Port) {
         let imp = self.imp();
+        port.set_visible(!self.ports_hidden());
+        let horizontal = imp.orientation.get() == gtk::Orientation::Horizontal;
 
         match port.direction() {
             Direction::Input => {
-                imp.grid.attach(&port, 0, imp.num_ports_in.get() + 1, 1, 1);
-                imp.num_ports_in.set(imp.num_ports_in.get() + 1);
+                let index = imp.num_ports_in.get();
+                if horizontal {
+                    imp.grid.attach(port, 0, index + 1, 1, 1);
+                } else {
+                    imp.grid.attach(port, index, 0, 1, 1);
+                }
+                imp.num_ports_in.set(index + 1);
             }
             Direction::Output => {
-                imp.grid.attach(&port, 1, imp.num_ports_out.get() + 1, 1, 1);
-                imp.num_ports_out.set(imp.num_ports_out.get() + 1);
+                let index = imp.num_ports_out.get();
+                if horizontal {
+                    imp.grid.attach(port, 1, index + 1, 1, 1);
+                } else {
+                    imp.grid.attach(port, index, 2, 1, 1);
+                }
+                imp.num_ports_out.set(index + 1);
             }
         }
+    }
+
+    /// Attach the title bar to the grid, spanning all port columns.
+    ///
+    /// In a horizontal orientation, the title bar sits above the ports,
+    /// in a vertical orientation, it sits between the row of inputs and the row of outputs.
+    fn attach_label(&self) {
+        let imp = self.imp();
+
+        imp.grid.remove(&imp.header);
+        if imp.orientation.get() == gtk::Orientation::Horizontal {
+            imp.grid.attach(&imp.header, 0, 0, 2, 1);
+        } else {
+            let columns = imp.num_ports_in.get().max(imp.num_ports_out.get()).max(1);
+            imp.grid.attach(&imp.header, 0, 1, columns, 1);
+        }
+    }
+
+    /// Get the number of ports of the node.
+    pub fn port_count(&self) -> usize {
+        self.imp().ports.borrow().len()
+    }
+
+    /// Show or hide the index of each of the nodes ports.
+    pub fn set_show_port_indices(&self, show: bool) {
+        for port in self.imp().ports.borrow().values() {
+            port.set_show_index(show);
+        }
+    }
 
-        imp.ports.borrow_mut().insert(id, port);
+    /// Find a port of the node by its name and direction.
+    pub fn port_by_name(&self, name: &str, direction: Direction) -> Option<super::port::Port> {
+        self.imp()
+            .ports
+            .borrow()
+            .values()
+            .find(|port| *port.direction() == direction && port.name() == name)
+            .cloned()
     }
 
     pub fn get_port(&self, id: u32) -> Option<super::port::Port> {
@@ -170,10 +718,35 @@ impl Node {
         if let Some(port) = imp.ports.borrow_mut().remove(&id) {
             match port.direction() {
                 Direction::Input => imp.num_ports_in.set(imp.num_ports_in.get() - 1),
-                Direction::Output => imp.num_ports_in.set(imp.num_ports_out.get() - 1),
+                Direction::Output => imp.num_ports_out.set(imp.num_ports_out.get() - 1),
             }
 
             port.unparent();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_ports_are_kept() {
+        let ports = HashSet::from([1, 2, 3]);
+        assert_eq!(port_changes(&ports, &ports), (vec![], vec![]));
+    }
+
+    #[test]
+    fn only_changed_ports_are_added_and_removed() {
+        let current = HashSet::from([1, 2, 3, 4]);
+        let new = HashSet::from([2, 4, 7, 5]);
+        assert_eq!(port_changes(&current, &new), (vec![1, 3], vec![5, 7]));
+    }
+
+    #[test]
+    fn all_ports_are_added_to_an_empty_node() {
+        let new = HashSet::from([9, 8]);
+        assert_eq!(port_changes(&HashSet::new(), &new), (vec![], vec![8, 9]));
+        assert_eq!(port_changes(&new, &HashSet::new()), (vec![8, 9], vec![]));
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2021 Tom A. Wagner <tom.a.wagner@protonmail.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License version 3 as published by
+// the Free Software Foundation.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use gtk::{gdk, glib, prelude::*, subclass::prelude::*};
+
+mod imp {
+    use glib::subclass::Signal;
+    use once_cell::sync::Lazy;
+
+    use super::*;
+
+    pub struct Note {
+        pub(super) content: gtk::Box,
+        /// The header is used to drag the note around, as dragging the text selects it instead.
+        pub(super) header: gtk::Box,
+        pub(super) remove_button: gtk::Button,
+        pub(super) text_view: gtk::TextView,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Note {
+        const NAME: &'static str = "HelvumNote";
+        type Type = super::Note;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+            klass.set_css_name("note");
+        }
+
+        fn new() -> Self {
+            let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+
+            let header = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+            header.add_css_class("header");
+            header.set_cursor(gdk::Cursor::from_name("grab", None).as_ref());
+
+            let remove_button = gtk::Button::from_icon_name("window-close-symbolic");
+            remove_button.add_css_class("flat");
+            remove_button.set_tooltip_text(Some("Remove Note"));
+            remove_button.set_halign(gtk::Align::End);
+            remove_button.set_hexpand(true);
+            header.append(&remove_button);
+            content.append(&header);
+
+            let text_view = gtk::TextView::new();
+            text_view.set_wrap_mode(gtk::WrapMode::WordChar);
+            text_view.set_size_request(160, 60);
+            content.append(&text_view);
+
+            Self {
+                content,
+                header,
+                remove_button,
+                text_view,
+            }
+        }
+    }
+
+    impl ObjectImpl for Note {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            self.content.set_parent(&*obj);
+
+            self.remove_button
+                .connect_clicked(glib::clone!(@weak obj => move |_| {
+                    obj.emit_by_name::<()>("remove-requested", &[]);
+                }));
+        }
+
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> =
+                Lazy::new(|| vec![glib::ParamSpecString::builder("text").build()]);
+
+            PROPERTIES.as_ref()
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "text" => {
+                    let buffer = self.text_view.buffer();
+                    let (start, end) = buffer.bounds();
+                    buffer.text(&start, &end, false).to_value()
+                }
+                _ => unimplemented!(),
+            }
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            match pspec.name() {
+                "text" => self
+                    .text_view
+                    .buffer()
+                    .set_text(value.get::<Option<&str>>().unwrap().unwrap_or_default()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> =
+                Lazy::new(|| vec![Signal::builder("remove-requested").build()]);
+
+            SIGNALS.as_ref()
+        }
+
+        fn dispose(&self) {
+            self.content.unparent();
+        }
+    }
+
+    impl WidgetImpl for Note {}
+}
+
+glib::wrapper! {
+    /// A text note that users can place anywhere on the canvas to annotate their graph.
+    pub struct Note(ObjectSubclass<imp::Note>)
+        @extends gtk::Widget;
+}
+
+impl Note {
+    pub fn new(text: &str) -> Self {
+        glib::Object::builder().property("text", &text).build()
+    }
+
+    /// Check whether the widget is the header that is used for dragging the note.
+    pub fn is_drag_handle(&self, widget: &gtk::Widget) -> bool {
+        widget == self.imp().header.upcast_ref::<gtk::Widget>()
+    }
+}
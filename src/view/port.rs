@@ -49,7 +49,11 @@ mod imp {
     pub struct Port {
         pub(super) pipewire_id: OnceCell<u32>,
         pub(super) label: gtk::Label,
+        /// Shows the ports index next to its name, if enabled.
+        pub(super) index_label: gtk::Label,
+        pub(super) index: OnceCell<Option<u32>>,
         pub(super) direction: OnceCell<Direction>,
+        pub(super) media_type: OnceCell<Option<MediaType>>,
     }
 
     #[glib::object_subclass]
@@ -59,7 +63,7 @@ mod imp {
         type ParentType = gtk::Widget;
 
         fn class_init(klass: &mut Self::Class) {
-            klass.set_layout_manager_type::<gtk::BinLayout>();
+            klass.set_layout_manager_type::<gtk::BoxLayout>();
 
             // Make it look like a GTK button.
             klass.set_css_name("button");
@@ -70,15 +74,37 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
 
-            self.label.set_parent(&*self.obj());
+            let obj = &*self.obj();
+            obj.layout_manager()
+                .and_then(|layout| layout.downcast::<gtk::BoxLayout>().ok())
+                .expect("Port has no box layout")
+                .set_spacing(6);
+
+            self.label.set_parent(obj);
             self.label.set_wrap(true);
             self.label.set_lines(2);
             self.label.set_max_width_chars(20);
             self.label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+
+            self.index_label.add_css_class("dim-label");
+            self.index_label.add_css_class("numeric");
+            self.index_label.set_visible(false);
+
+            obj.set_has_tooltip(true);
+            obj.connect_query_tooltip(|port, _, _, _, tooltip| {
+                // Short names are shown in full already, so only show a tooltip when the name is cut off.
+                if !port.imp().label.layout().is_ellipsized() {
+                    return false;
+                }
+
+                tooltip.set_text(Some(&port.describe()));
+                true
+            });
         }
 
         fn dispose(&self) {
-            self.label.unparent()
+            self.label.unparent();
+            self.index_label.unparent();
         }
 
         fn properties() -> &'static [glib::ParamSpec] {
@@ -106,7 +132,6 @@ mod imp {
             match pspec.name() {
                 "name" => {
                     self.label.set_text(value.get().unwrap());
-                    self.label.set_tooltip_text(value.get().ok());
                 }
                 "pipewire-id" => self.pipewire_id.set(value.get().unwrap()).unwrap(),
                 _ => unimplemented!(),
@@ -133,7 +158,13 @@ glib::wrapper! {
 }
 
 impl Port {
-    pub fn new(id: u32, name: &str, direction: Direction, media_type: Option<MediaType>) -> Self {
+    pub fn new(
+        id: u32,
+        name: &str,
+        direction: Direction,
+        media_type: Option<MediaType>,
+        index: Option<u32>,
+    ) -> Self {
         // Create the widget and initialize needed fields
         let res: Self = glib::Object::builder()
             .property("pipewire-id", &id)
@@ -145,6 +176,19 @@ impl Port {
         imp.direction
             .set(direction)
             .expect("Port direction already set");
+        imp.media_type
+            .set(media_type)
+            .expect("Port media type already set");
+        imp.index.set(index).expect("Port index already set");
+
+        // Keep the index on the outer side of the port, where the links attach.
+        if let Some(index) = index {
+            imp.index_label.set_text(&index.to_string());
+        }
+        match direction {
+            Direction::Input => imp.index_label.insert_before(&res, Some(&imp.label)),
+            Direction::Output => imp.index_label.insert_after(&res, Some(&imp.label)),
+        }
 
         // Add a drag source and drop target controller with the type depending on direction,
         // they will be responsible for link creation by dragging an output port onto an input port or the other way around.
@@ -162,6 +206,18 @@ impl Port {
             trace!("Drag started from port {}", id);
             let paintable = gtk::WidgetPaintable::new(Some(&obj));
             source.set_icon(Some(&paintable), 0, 0);
+
+            // Draw the link being dragged out on the graph, ending at the port itself until the pointer moves.
+            let Some(graphview) = obj.graphview() else { return };
+            let (x, y) = (f64::from(obj.width()) / 2.0, f64::from(obj.height()) / 2.0);
+            if let Some((x, y)) = obj.translate_coordinates(&graphview, x, y) {
+                graphview.begin_link_preview(id, x, y);
+            }
+        }));
+        drag_src.connect_drag_end(clone!(@weak res as obj => move |_, _, _| {
+            if let Some(graphview) = obj.graphview() {
+                graphview.end_link_preview();
+            }
         }));
         drag_src.connect_drag_cancel(move |_, _, _| {
             trace!("Drag from port {} was cancelled", id);
@@ -242,10 +298,54 @@ impl Port {
         self.set_property("name", name);
     }
 
+    /// Show or hide the ports index next to its name.
+    ///
+    /// Ports without a known index never show one.
+    pub fn set_show_index(&self, show: bool) {
+        let imp = self.imp();
+        let has_index = matches!(imp.index.get(), Some(Some(_)));
+        imp.index_label.set_visible(show && has_index);
+    }
+
+    /// Describe the port with its full name, direction and media type, e.g. for its tooltip.
+    fn describe(&self) -> String {
+        let direction = match self.direction() {
+            Direction::Input => "Input",
+            Direction::Output => "Output",
+        };
+        let media_type = match self.media_type() {
+            Some(MediaType::Audio) => "Audio",
+            Some(MediaType::Video) => "Video",
+            Some(MediaType::Midi) => "MIDI",
+            None => "Unknown",
+        };
+
+        format!(
+            "{}\nDirection: {}\nMedia type: {}",
+            self.name(),
+            direction,
+            media_type
+        )
+    }
+
+    /// Get the graph view the port is shown on.
+    fn graphview(&self) -> Option<super::GraphView> {
+        self.ancestor(super::GraphView::static_type())
+            .and_then(|graphview| graphview.downcast().ok())
+    }
+
     pub fn direction(&self) -> &Direction {
         self.imp()
             .direction
             .get()
             .expect("Port direction is not set")
     }
+
+    pub fn media_type(&self) -> Option<MediaType> {
+        *self
+            .imp()
+            .media_type
+            .get()
+            .expect("Port media type is not set")
+    }
 }
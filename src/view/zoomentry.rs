@@ -68,7 +68,10 @@ mod imp {
                 .connect_clicked(clone!(@weak self as imp => move |_| {
                     let graphview = imp.graphview.borrow();
                     if let Some(ref graphview) = *graphview {
-                        graphview.set_zoom_factor(graphview.zoom_factor() - 0.1, None);
+                        graphview.set_zoom_factor(
+                            graphview.zoom_factor() - view::GraphView::ZOOM_STEP,
+                            None,
+                        );
                     }
                 }));
 
@@ -76,7 +79,10 @@ mod imp {
                 .connect_clicked(clone!(@weak self as imp => move |_| {
                     let graphview = imp.graphview.borrow();
                     if let Some(ref graphview) = *graphview {
-                        graphview.set_zoom_factor(graphview.zoom_factor() + 0.1, None);
+                        graphview.set_zoom_factor(
+                            graphview.zoom_factor() + view::GraphView::ZOOM_STEP,
+                            None,
+                        );
                     }
                 }));
 